@@ -154,30 +154,26 @@ pub fn validate_plan(plan: &ActionPlan, allowlist: &Allowlist) -> Vec<AllowlistV
                 contract_id,
                 function,
                 ..
-            } => {
-                if !allowlist.is_contract_allowed(contract_id, function) {
-                    violations.push(AllowlistViolation {
-                        index: idx,
-                        action: action.kind().to_string(),
-                        reason: format!("contract {contract_id}:{function} not in allowlist"),
-                    });
-                }
+            } if !allowlist.is_contract_allowed(contract_id, function) => {
+                violations.push(AllowlistViolation {
+                    index: idx,
+                    action: action.kind().to_string(),
+                    reason: format!("contract {contract_id}:{function} not in allowlist"),
+                });
             }
             Action::StellarPayment {
                 asset_code,
                 asset_issuer,
                 ..
-            } => {
-                if !allowlist.is_asset_allowed(asset_code, asset_issuer.as_deref()) {
-                    violations.push(AllowlistViolation {
-                        index: idx,
-                        action: action.kind().to_string(),
-                        reason: format!(
-                            "asset {asset_code}:{} not in allowlist",
-                            asset_issuer.as_deref().unwrap_or("")
-                        ),
-                    });
-                }
+            } if !allowlist.is_asset_allowed(asset_code, asset_issuer.as_deref()) => {
+                violations.push(AllowlistViolation {
+                    index: idx,
+                    action: action.kind().to_string(),
+                    reason: format!(
+                        "asset {asset_code}:{} not in allowlist",
+                        asset_issuer.as_deref().unwrap_or("")
+                    ),
+                });
             }
             Action::StellarChangeTrust {
                 asset_code,
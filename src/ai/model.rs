@@ -1,19 +1,80 @@
 //! AI model loader + classifier (CPU ONNX).
+//!
+//! Model IDs and [`ModelKind`] are always available. Loading and inference need the `onnx`
+//! feature (on by default); without it, e.g. for a WASM build, [`AIModel`] can't be
+//! constructed and every load fails, so scripts take their no-model fallbacks.
 
-use std::{path::Path, rc::Rc};
+#[cfg(feature = "onnx")]
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use std::{env, sync::OnceLock};
 
 use anyhow::{anyhow, Result};
+#[cfg(feature = "onnx")]
 use tokenizers::{
     PaddingDirection, PaddingParams, Tokenizer, TruncationDirection, TruncationParams,
     TruncationStrategy,
 };
+#[cfg(feature = "onnx")]
 use tract_ndarray::prelude::{Array as TractArray, Ix2 as TractIx2, IxDyn as TractIxDyn};
+#[cfg(feature = "onnx")]
 use tract_onnx::prelude::*;
 
+/// Known model IDs (canonical ID first, then aliases) and their directory under the models
+/// root. Shared by the server's `model` field and `set x from AI "id": ...` in scripts.
+pub const MODEL_IDS: &[(&[&str], &str)] = &[
+    (&["sst2"], "distilbert-sst2"),
+    (&["factcheck"], "factcheck"),
+    (&["intent"], "intent"),
+    (&["intent_stellar", "stellar_intent"], "intent_stellar"),
+    (&["toxic"], "toxic_quantized"),
+    (
+        &["macro", "intent_macro", "macro_intent", "gpt2", "generator"],
+        "intent_macro",
+    ),
+];
+
+/// Directory (under the models root) for a model ID or alias.
+pub fn model_dir_for_id(id: &str) -> Option<&'static str> {
+    MODEL_IDS
+        .iter()
+        .find(|(ids, _)| ids.contains(&id))
+        .map(|(_, dir)| *dir)
+}
+
+static DEFAULT_MODELS_ROOT: OnceLock<String> = OnceLock::new();
+
+/// Directory model IDs resolve under: `NC_MODELS_DIR`, else the default set with
+/// [`set_default_models_root`], else `models`. Scripts (`# @model`, `from AI "id"`) and the
+/// servers' `model` field all go through here, so they agree on the paths.
+pub fn models_root() -> String {
+    env::var("NC_MODELS_DIR").unwrap_or_else(|_| {
+        DEFAULT_MODELS_ROOT
+            .get()
+            .map_or("models", String::as_str)
+            .to_string()
+    })
+}
+
+/// Default for [`models_root`] when `NC_MODELS_DIR` is unset (the servers use
+/// `/opt/neurochain/models`). Only the first call counts.
+pub fn set_default_models_root(path: impl Into<String>) {
+    let _ = DEFAULT_MODELS_ROOT.set(path.into());
+}
+
+/// `{models_root}/{dir}/model.onnx` for a model ID or alias; `None` for an unknown ID.
+pub fn resolve_model_path(models_root: &str, id: &str) -> Option<String> {
+    model_dir_for_id(id).map(|dir| format!("{models_root}/{dir}/model.onnx"))
+}
+
+#[cfg(feature = "onnx")]
 type TractPlan = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
 
 /* -------------------------------------------------------------------------- */
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ModelKind {
     SST2,
     Toxic,
@@ -24,24 +85,10 @@ pub enum ModelKind {
     Unknown,
 }
 
-#[derive(Clone)]
-pub struct AIModel {
-    plan: Rc<TractPlan>,
-    tokenizer: Tokenizer,
-    model_kind: ModelKind,
-    pad_token: String,
-}
-
-/* ========================================================================== */
-impl AIModel {
-    /* ---- loader ------------------------------------------------------- */
-    pub fn new(model_path: &str) -> Result<Self> {
-        if !Path::new(model_path).exists() {
-            return Err(anyhow!("Model file not found: {model_path}"));
-        }
-
-        /* Model type (heuristic from file path) */
-        let model_kind = if model_path.contains("intent_macro") {
+impl ModelKind {
+    /// Model type from its file path (heuristic: the bundled directory names).
+    pub fn from_path(model_path: &str) -> Self {
+        if model_path.contains("intent_macro") {
             ModelKind::MacroIntent
         } else if model_path.contains("intent_stellar") {
             ModelKind::IntentStellar
@@ -55,61 +102,18 @@ impl AIModel {
             ModelKind::Intent
         } else {
             ModelKind::Unknown
-        };
-
-        /* Tokenizer path = same directory as model.onnx */
-        let tok_path = Path::new(model_path)
-            .parent()
-            .ok_or_else(|| anyhow!("Tokenizer directory missing"))?
-            .join("tokenizer.json");
-        let (tokenizer, pad_token) = Self::prepare_tokenizer(&tok_path, &model_kind)?;
-
-        let plan = tract_onnx::onnx()
-            .model_for_path(model_path)?
-            .into_optimized()?
-            .into_runnable()?;
-
-        Ok(Self {
-            plan: Rc::new(plan),
-            tokenizer,
-            model_kind,
-            pad_token,
-        })
-    }
-    /* ---- inference ---------------------------------------------------- */
-    pub fn predict(&self, text: &str) -> Result<String> {
-        let (label, _) = self.predict_with_score(text)?;
-        Ok(label)
+        }
     }
 
-    pub fn kind(&self) -> ModelKind {
-        self.model_kind.clone()
+    /// Kind for a model ID or alias from [`MODEL_IDS`] (`"sst2"`, `"toxic"`, ...).
+    pub fn from_id(id: &str) -> Option<Self> {
+        model_dir_for_id(id).map(Self::from_path)
     }
 
-    /// Returns (label, softmax score)
-    pub fn predict_with_score(&self, text: &str) -> Result<(String, f32)> {
-        let mut enc = self.tokenizer.encode(text, true).map_err(|e| anyhow!(e))?;
-        enc.pad(128, 0, 0, self.pad_token.as_str(), PaddingDirection::Left);
-        enc.truncate(128, 0, TruncationDirection::Right);
-
-        let ids = TractArray::from_shape_vec(
-            TractIxDyn(&[1, 128]),
-            enc.get_ids().iter().map(|&id| id as i64).collect(),
-        )?
-        .into_tensor();
-        let mask = TractArray::from_shape_vec(
-            TractIxDyn(&[1, 128]),
-            enc.get_attention_mask().iter().map(|&m| m as i64).collect(),
-        )?
-        .into_tensor();
-
-        let outs = self.plan.run(tvec![ids.into(), mask.into()])?;
-        let logits = outs[0]
-            .to_array_view::<f32>()?
-            .into_dimensionality::<TractIx2>()?;
-        let row = logits.row(0);
-
-        let labels: &[&str] = match self.model_kind {
+    /// Built-in output labels, indexed like the classifier logits. A `labels.json` next to
+    /// the model overrides these (see `load_labels`).
+    pub fn labels(&self) -> &'static [&'static str] {
+        match self {
             ModelKind::SST2 => &["Negative", "Positive"],
             ModelKind::Toxic => &["Toxic", "Not toxic"],
             ModelKind::FactCheck => &["entailment", "neutral", "contradiction"],
@@ -138,19 +142,280 @@ impl AIModel {
                 "Unknown",
             ],
             ModelKind::Unknown => &["unknown"],
-        };
-        let (best_idx, prob) = argmax_with_prob(row.iter().copied());
-        let label = labels
-            .get(best_idx)
-            .copied()
-            .unwrap_or("unknown")
-            .to_string();
+        }
+    }
+
+    /// Padding side the model was trained with.
+    ///
+    /// Every bundled model is a DistilBERT classifier, and BERT-family encoders read the
+    /// `[CLS]` position at index 0: padding them on the left shifts `[CLS]` away from that
+    /// slot and silently costs accuracy. GPT-style models read the last position, so they
+    /// want left padding; `Unknown` keeps the historical left default for those.
+    /// A `padding_side` in the model's `tokenizer_config.json` overrides this.
+    #[cfg(feature = "onnx")]
+    pub fn default_padding(&self) -> PaddingDirection {
+        match self {
+            ModelKind::SST2
+            | ModelKind::Toxic
+            | ModelKind::FactCheck
+            | ModelKind::Intent
+            | ModelKind::IntentStellar
+            | ModelKind::MacroIntent => PaddingDirection::Right,
+            ModelKind::Unknown => PaddingDirection::Left,
+        }
+    }
+}
+
+/// Cheap to clone: the optimized plan is shared behind an `Arc`, so clones (and the
+/// process-wide cache below) never re-parse the ONNX graph. `AIModel` is `Send + Sync`;
+/// inference only needs `&self`, so the server can run it from `spawn_blocking` threads.
+#[cfg(feature = "onnx")]
+#[derive(Clone)]
+pub struct AIModel {
+    plan: Arc<TractPlan>,
+    encoder: TextEncoder,
+    model_kind: ModelKind,
+    labels: Arc<Vec<String>>,
+    /// `"problem_type": "multi_label_classification"` in `config.json`: one sigmoid per class.
+    multi_label: bool,
+}
+
+/// Tokenizer plus the per-model input settings (padding side, sequence length).
+#[cfg(feature = "onnx")]
+#[derive(Clone)]
+struct TextEncoder {
+    tokenizer: Tokenizer,
+    pad_token: String,
+    padding: PaddingDirection,
+    max_length: usize,
+}
 
-        Ok((label, prob))
+/* ========================================================================== */
+#[cfg(feature = "onnx")]
+impl AIModel {
+    /* ---- loader ------------------------------------------------------- */
+    pub fn new(model_path: &str) -> Result<Self> {
+        if !Path::new(model_path).exists() {
+            return Err(anyhow!("Model file not found: {model_path}"));
+        }
+
+        let model_kind = ModelKind::from_path(model_path);
+
+        /* Tokenizer path = same directory as model.onnx */
+        let model_dir = Path::new(model_path)
+            .parent()
+            .ok_or_else(|| anyhow!("Tokenizer directory missing"))?;
+        let encoder = TextEncoder::load(model_dir, &model_kind)?;
+        let labels = load_labels(model_dir, &model_kind)?;
+        let multi_label = multi_label_from_config(model_dir);
+
+        let plan = tract_onnx::onnx()
+            .model_for_path(model_path)?
+            .into_optimized()?
+            .into_runnable()?;
+
+        Ok(Self {
+            plan: Arc::new(plan),
+            encoder,
+            model_kind,
+            labels: Arc::new(labels),
+            multi_label,
+        })
+    }
+    /// Like [`AIModel::new`], but loads each path at most once per process and hands out
+    /// clones afterwards. Failed loads are not cached, so a model copied in later is picked up.
+    ///
+    /// The cache is guarded by a mutex that is never held while a model is being loaded;
+    /// two threads racing on the same cold path may both load it, and the first insert wins.
+    /// Entries live for the rest of the process: replacing a model file needs a restart.
+    pub fn load_cached(model_path: &str) -> Result<Self> {
+        static CACHE: OnceLock<Mutex<HashMap<String, AIModel>>> = OnceLock::new();
+        let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+        let key = std::fs::canonicalize(model_path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| model_path.to_string());
+
+        if let Some(hit) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(hit.clone());
+        }
+        let loaded = Self::new(model_path)?;
+        Ok(cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key)
+            .or_insert(loaded)
+            .clone())
+    }
+
+    /* ---- inference ---------------------------------------------------- */
+    pub fn predict(&self, text: &str) -> Result<String> {
+        let (label, _) = self.predict_with_score(text)?;
+        Ok(label)
+    }
+
+    pub fn kind(&self) -> ModelKind {
+        self.model_kind
+    }
+
+    /// Returns (label, softmax score)
+    pub fn predict_with_score(&self, text: &str) -> Result<(String, f32)> {
+        Ok(best(self.predict_all(text)?))
+    }
+
+    /// `predict_with_score` for many inputs in one `[N, max_length]` run of the model, in
+    /// input order. Same results as calling it once per text, minus the per-call overhead.
+    pub fn predict_batch(&self, texts: &[&str]) -> Result<Vec<(String, f32)>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .logits_batch(texts)?
+            .into_iter()
+            .map(|row| best(self.scored(row)))
+            .collect())
+    }
+
+    /// Every label paired with its softmax probability, in the model's label order.
+    /// The probabilities sum to 1.
+    pub fn predict_all(&self, text: &str) -> Result<Vec<(String, f32)>> {
+        Ok(self.scored(self.logits(text)?))
+    }
+
+    /// The `k` most likely labels with their softmax scores, best first. Ties keep the model's
+    /// label order, so the first entry is the `predict_with_score` result.
+    pub fn predict_topk(&self, text: &str, k: usize) -> Result<Vec<(String, f32)>> {
+        Ok(top_k(self.predict_all(text)?, k))
+    }
+
+    /// Multi-label prediction: every label whose sigmoid score is above `threshold`, in the
+    /// model's label order. Only for models whose `config.json` declares
+    /// `"problem_type": "multi_label_classification"`; softmax-trained logits would give
+    /// meaningless per-class scores.
+    pub fn predict_multi(&self, text: &str, threshold: f32) -> Result<Vec<(String, f32)>> {
+        if !self.multi_label {
+            return Err(anyhow!(
+                "Model is not multi-label (config.json has no \"problem_type\": \"multi_label_classification\")"
+            ));
+        }
+        Ok(labels_above(&self.labels, &self.logits(text)?, threshold))
+    }
+
+    pub fn is_multi_label(&self) -> bool {
+        self.multi_label
+    }
+
+    /// Raw logits of the first (only) batch row.
+    fn logits(&self, text: &str) -> Result<Vec<f32>> {
+        self.logits_batch(&[text])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Model returned no logits"))
+    }
+
+    /// Raw logits, one row per input text.
+    fn logits_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let (ids, mask) = self.encoder.encode_batch(texts)?;
+        let outs = self.plan.run(tvec![ids.into(), mask.into()])?;
+        let logits = outs[0]
+            .to_array_view::<f32>()?
+            .into_dimensionality::<TractIx2>()?;
+        Ok(logits
+            .rows()
+            .into_iter()
+            .map(|row| row.iter().copied().collect())
+            .collect())
+    }
+
+    /// Softmax probabilities paired with their labels.
+    fn scored(&self, logits: Vec<f32>) -> Vec<(String, f32)> {
+        softmax(logits)
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| (self.label(i).to_string(), p))
+            .collect()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        self.labels.get(i).map(String::as_str).unwrap_or("unknown")
+    }
+}
+
+/// Without the `onnx` feature there is no model to hold: the type is uninhabited and every
+/// load fails, so `set x from AI:` stores its `else` default or the prompt and macros use the
+/// offline keyword fallback.
+#[cfg(not(feature = "onnx"))]
+#[derive(Clone)]
+pub struct AIModel(std::convert::Infallible);
+
+#[cfg(not(feature = "onnx"))]
+impl AIModel {
+    pub fn new(model_path: &str) -> Result<Self> {
+        Err(anyhow!(
+            "Cannot load {model_path}: built without the `onnx` feature"
+        ))
+    }
+
+    pub fn load_cached(model_path: &str) -> Result<Self> {
+        Self::new(model_path)
+    }
+
+    pub fn predict(&self, _text: &str) -> Result<String> {
+        match self.0 {}
+    }
+
+    pub fn kind(&self) -> ModelKind {
+        match self.0 {}
+    }
+
+    pub fn predict_with_score(&self, _text: &str) -> Result<(String, f32)> {
+        match self.0 {}
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+#[cfg(feature = "onnx")]
+impl TextEncoder {
+    /// Reads `tokenizer.json` (plus optional `tokenizer_config.json` / `config.json`) from
+    /// the model directory.
+    fn load(model_dir: &Path, kind: &ModelKind) -> Result<Self> {
+        let padding = padding_direction(model_dir, kind);
+        let max_length = max_length_from_config(model_dir);
+        let (tokenizer, pad_token) =
+            Self::prepare_tokenizer(&model_dir.join("tokenizer.json"), padding, max_length)?;
+        Ok(Self {
+            tokenizer,
+            pad_token,
+            padding,
+            max_length,
+        })
+    }
+
+    /// Token ids and attention mask, both shaped `[texts.len(), max_length]`; row `i` is
+    /// `texts[i]`.
+    fn encode_batch(&self, texts: &[&str]) -> Result<(Tensor, Tensor)> {
+        let len = self.max_length;
+        let mut ids = Vec::with_capacity(texts.len() * len);
+        let mut mask = Vec::with_capacity(texts.len() * len);
+        for text in texts {
+            let mut enc = self.tokenizer.encode(*text, true).map_err(|e| anyhow!(e))?;
+            enc.pad(len, 0, 0, self.pad_token.as_str(), self.padding);
+            enc.truncate(len, 0, TruncationDirection::Right);
+            ids.extend(enc.get_ids().iter().map(|&id| id as i64));
+            mask.extend(enc.get_attention_mask().iter().map(|&m| m as i64));
+        }
+
+        let shape = [texts.len(), len];
+        let ids = TractArray::from_shape_vec(TractIxDyn(&shape), ids)?.into_tensor();
+        let mask = TractArray::from_shape_vec(TractIxDyn(&shape), mask)?.into_tensor();
+        Ok((ids, mask))
     }
 
     /* ---- tokenizer helper -------------------------------------------- */
-    fn prepare_tokenizer(path: &Path, _kind: &ModelKind) -> Result<(Tokenizer, String)> {
+    fn prepare_tokenizer(
+        path: &Path,
+        padding: PaddingDirection,
+        max_length: usize,
+    ) -> Result<(Tokenizer, String)> {
         let mut tok = Tokenizer::from_file(path).map_err(|e| anyhow!(e))?;
 
         let candidates = ["[PAD]", "<pad>", "<PAD>", "PAD"];
@@ -165,15 +430,15 @@ impl AIModel {
             .ok_or_else(|| anyhow!("Pad token \"{}\" not found in tokenizer", pad_token))?;
 
         tok.with_padding(Some(PaddingParams {
-            strategy: tokenizers::PaddingStrategy::Fixed(128),
-            direction: PaddingDirection::Left,
+            strategy: tokenizers::PaddingStrategy::Fixed(max_length),
+            direction: padding,
             pad_to_multiple_of: None,
             pad_id,
             pad_type_id: 0,
             pad_token: pad_token.clone(),
         }));
         let _ = tok.with_truncation(Some(TruncationParams {
-            max_length: 128,
+            max_length,
             strategy: TruncationStrategy::LongestFirst,
             stride: 0,
             direction: TruncationDirection::Right,
@@ -183,24 +448,179 @@ impl AIModel {
     }
 }
 
-/* -------------------------------------------------------------------------- */
-fn argmax_with_prob<I>(iter: I) -> (usize, f32)
+/// Class labels in logit order.
+///
+/// 1. `labels.json` next to the model: `["neg", "pos"]` or `{"0": "neg", "1": "pos"}`.
+/// 2. `id2label` from `config.json`, for `ModelKind::Unknown` only. The bundled models keep
+///    their built-in names, since scripts compare against them (`if mood == "Positive":`)
+///    and exported configs often carry different casing or `LABEL_0`-style placeholders.
+/// 3. The built-in defaults for the kind.
+///
+/// A `labels.json` that exists but can't be read as labels is an error rather than a
+/// silent fallback, so a typo doesn't quietly mislabel every prediction.
+#[cfg(feature = "onnx")]
+fn load_labels(model_dir: &Path, kind: &ModelKind) -> Result<Vec<String>> {
+    let labels_path = model_dir.join("labels.json");
+    if labels_path.exists() {
+        let raw = std::fs::read_to_string(&labels_path)?;
+        let value: serde_json::Value = serde_json::from_str(&raw)
+            .map_err(|e| anyhow!("Invalid {}: {e}", labels_path.display()))?;
+        return labels_from_json(&value).ok_or_else(|| {
+            anyhow!(
+                "{} must be a list or an index map of strings",
+                labels_path.display()
+            )
+        });
+    }
+
+    if *kind == ModelKind::Unknown {
+        let id2label = std::fs::read_to_string(model_dir.join("config.json"))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+            .and_then(|cfg| labels_from_json(cfg.get("id2label")?));
+        if let Some(labels) = id2label {
+            return Ok(labels);
+        }
+    }
+
+    Ok(kind.labels().iter().map(|l| l.to_string()).collect())
+}
+
+/// Accepts `["a", "b"]` or `{"0": "a", "1": "b"}` (indices must be exactly 0..n).
+#[cfg(feature = "onnx")]
+fn labels_from_json(value: &serde_json::Value) -> Option<Vec<String>> {
+    match value {
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(|v| v.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .filter(|v| !v.is_empty()),
+        serde_json::Value::Object(map) => {
+            let mut labels = vec![None; map.len()];
+            for (idx, label) in map {
+                let slot = labels.get_mut(idx.parse::<usize>().ok()?)?;
+                *slot = Some(label.as_str()?.to_string());
+            }
+            labels
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .filter(|v| !v.is_empty())
+        }
+        _ => None,
+    }
+}
+
+/// Sequence length used when the model directory doesn't say otherwise.
+#[cfg(feature = "onnx")]
+pub const DEFAULT_MAX_LENGTH: usize = 128;
+
+/// Sequence length from the sidecar `config.json`.
+///
+/// `nc_max_length` wins when present. Otherwise the default is kept, capped by
+/// `max_position_embeddings`: that field is the architecture limit (512 for DistilBERT), not
+/// the length the ONNX graph was exported with, so it is never used to grow the input.
+#[cfg(feature = "onnx")]
+fn max_length_from_config(model_dir: &Path) -> usize {
+    let cfg = std::fs::read_to_string(model_dir.join("config.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok());
+    let field = |name: &str| {
+        cfg.as_ref()
+            .and_then(|c| c.get(name)?.as_u64())
+            .filter(|&n| n > 0)
+            .map(|n| n as usize)
+    };
+    match (field("nc_max_length"), field("max_position_embeddings")) {
+        (Some(n), _) => n,
+        (None, Some(limit)) => DEFAULT_MAX_LENGTH.min(limit),
+        (None, None) => DEFAULT_MAX_LENGTH,
+    }
+}
+
+/// Padding side from the sidecar `tokenizer_config.json` (`"padding_side": "left" | "right"`),
+/// falling back to the per-kind default.
+#[cfg(feature = "onnx")]
+fn padding_direction(model_dir: &Path, kind: &ModelKind) -> PaddingDirection {
+    let side = std::fs::read_to_string(model_dir.join("tokenizer_config.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|cfg| {
+            cfg.get("padding_side")?
+                .as_str()
+                .map(str::to_ascii_lowercase)
+        });
+    match side.as_deref() {
+        Some("left") => PaddingDirection::Left,
+        Some("right") => PaddingDirection::Right,
+        _ => kind.default_padding(),
+    }
+}
+
+/// Whether the sidecar `config.json` marks the model as multi-label (Hugging Face
+/// `problem_type`).
+#[cfg(feature = "onnx")]
+fn multi_label_from_config(model_dir: &Path) -> bool {
+    std::fs::read_to_string(model_dir.join("config.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|cfg| Some(cfg.get("problem_type")?.as_str()? == "multi_label_classification"))
+        .unwrap_or(false)
+}
+
+#[cfg(feature = "onnx")]
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Labels whose sigmoid score is strictly above `threshold`, paired with the score.
+/// Logits past the end of `labels` are named `unknown`.
+#[cfg(feature = "onnx")]
+fn labels_above(labels: &[String], logits: &[f32], threshold: f32) -> Vec<(String, f32)> {
+    logits
+        .iter()
+        .enumerate()
+        .map(|(i, &l)| (i, sigmoid(l)))
+        .filter(|&(_, p)| p > threshold)
+        .map(|(i, p)| {
+            let label = labels.get(i).map(String::as_str).unwrap_or("unknown");
+            (label.to_string(), p)
+        })
+        .collect()
+}
+
+/// The highest-scoring label; the first maximum wins on ties, matching the label order.
+#[cfg(feature = "onnx")]
+fn best(scored: Vec<(String, f32)>) -> (String, f32) {
+    scored
+        .into_iter()
+        .reduce(|best, cand| if cand.1 > best.1 { cand } else { best })
+        .unwrap_or_else(|| ("unknown".to_string(), 0.0))
+}
+
+/// Highest scores first (stable, so equal scores keep their order), at most `k` of them.
+#[cfg(feature = "onnx")]
+fn top_k(mut scored: Vec<(String, f32)>, k: usize) -> Vec<(String, f32)> {
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
+
+/// Numerically stable softmax (shifts by the max logit before exponentiating).
+#[cfg(feature = "onnx")]
+fn softmax<I>(logits: I) -> Vec<f32>
 where
     I: IntoIterator<Item = f32>,
 {
-    let vals: Vec<f32> = iter.into_iter().collect();
-    let mut best_idx = 0;
-    let mut best_val = f32::MIN;
-    for (i, v) in vals.iter().enumerate() {
-        if v > &best_val {
-            best_idx = i;
-            best_val = *v;
-        }
+    let vals: Vec<f32> = logits.into_iter().collect();
+    let max = vals.iter().copied().fold(f32::MIN, f32::max);
+    let exps: Vec<f32> = vals.iter().map(|v| (v - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    if sum > 0.0 {
+        exps.into_iter().map(|e| e / sum).collect()
+    } else {
+        vec![0.0; vals.len()]
     }
-    let exp_sum: f32 = vals.iter().map(|v| (*v - best_val).exp()).sum();
-    let prob = if exp_sum > 0.0 { 1.0 / exp_sum } else { 0.0 };
-    (best_idx, prob)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "onnx"))]
 mod tests;
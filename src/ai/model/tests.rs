@@ -1,6 +1,29 @@
-use super::AIModel;
+use super::{padding_direction, AIModel, ModelKind, TextEncoder, DEFAULT_MAX_LENGTH};
 use anyhow::Result;
+use std::collections::HashMap;
 use std::path::Path;
+use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::{PaddingDirection, Tokenizer};
+use tract_onnx::prelude::tract_ndarray::Axis;
+
+/// Writes a tiny word-level `tokenizer.json` (no ONNX model needed) into `dir`.
+fn write_fixture_tokenizer(dir: &Path) -> std::path::PathBuf {
+    let vocab: HashMap<String, u32> = [("[PAD]", 0), ("[UNK]", 1), ("hello", 2), ("world", 3)]
+        .into_iter()
+        .map(|(w, id)| (w.to_string(), id))
+        .collect();
+    let model = WordLevel::builder()
+        .vocab(vocab)
+        .unk_token("[UNK]".into())
+        .build()
+        .expect("word-level model");
+    let mut tok = Tokenizer::new(model);
+    tok.with_pre_tokenizer(Whitespace {});
+    let path = dir.join("tokenizer.json");
+    tok.save(&path, false).expect("save tokenizer fixture");
+    path
+}
 
 fn should_skip(model_path: &str) -> bool {
     if Path::new(model_path).exists() {
@@ -92,3 +115,321 @@ fn test_intent_stellar_model_loading() -> Result<()> {
     .contains(&result.as_str()));
     Ok(())
 }
+
+#[test]
+fn bert_family_defaults_to_right_padding() {
+    assert!(matches!(
+        ModelKind::SST2.default_padding(),
+        PaddingDirection::Right
+    ));
+    assert!(matches!(
+        ModelKind::MacroIntent.default_padding(),
+        PaddingDirection::Right
+    ));
+    assert!(matches!(
+        ModelKind::Unknown.default_padding(),
+        PaddingDirection::Left
+    ));
+}
+
+#[test]
+fn configured_padding_direction_is_applied() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = write_fixture_tokenizer(dir.path());
+
+    let (right, _) =
+        TextEncoder::prepare_tokenizer(&path, PaddingDirection::Right, DEFAULT_MAX_LENGTH)?;
+    let ids = right
+        .encode("hello world", false)
+        .unwrap()
+        .get_ids()
+        .to_vec();
+    assert_eq!(ids.len(), 128);
+    assert_eq!(&ids[..3], &[2, 3, 0]);
+
+    let (left, _) =
+        TextEncoder::prepare_tokenizer(&path, PaddingDirection::Left, DEFAULT_MAX_LENGTH)?;
+    let ids = left
+        .encode("hello world", false)
+        .unwrap()
+        .get_ids()
+        .to_vec();
+    assert_eq!(&ids[125..], &[0, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn sidecar_padding_side_overrides_kind_default() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    assert!(matches!(
+        padding_direction(dir.path(), &ModelKind::SST2),
+        PaddingDirection::Right
+    ));
+
+    std::fs::write(
+        dir.path().join("tokenizer_config.json"),
+        r#"{"padding_side": "left"}"#,
+    )?;
+    assert!(matches!(
+        padding_direction(dir.path(), &ModelKind::SST2),
+        PaddingDirection::Left
+    ));
+    Ok(())
+}
+
+#[test]
+fn model_is_shareable_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<AIModel>();
+}
+
+#[test]
+fn load_cached_does_not_cache_failures() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let missing = dir.path().join("model.onnx");
+    let missing = missing.to_str().unwrap();
+
+    let err = AIModel::load_cached(missing).err().expect("missing model");
+    assert!(err.to_string().contains("Model file not found"));
+    // Still a miss (and still the same error) on the second call: nothing was cached.
+    assert!(AIModel::load_cached(missing).is_err());
+}
+
+#[test]
+fn softmax_probabilities_sum_to_one() {
+    let probs = super::softmax([2.0, 1.0, 0.1, -3.0]);
+    assert_eq!(probs.len(), 4);
+    let sum: f32 = probs.iter().sum();
+    assert!((sum - 1.0).abs() < 1e-5, "sum was {sum}");
+    assert!(probs.windows(2).all(|w| w[0] > w[1]));
+
+    // Large logits must not overflow.
+    let probs = super::softmax([1000.0, 1000.0]);
+    assert!((probs[0] - 0.5).abs() < 1e-6 && (probs[1] - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn top_k_sorts_by_score_and_keeps_ties_in_label_order() {
+    let scored = ["a", "b", "c", "d"]
+        .into_iter()
+        .map(String::from)
+        .zip([0.1, 0.4, 0.4, 0.1])
+        .collect();
+    let top = super::top_k(scored, 3);
+    let names: Vec<&str> = top.iter().map(|(l, _)| l.as_str()).collect();
+    assert_eq!(names, ["b", "c", "a"]);
+    assert!(super::top_k(Vec::new(), 3).is_empty());
+}
+
+#[test]
+fn multi_label_keeps_every_sigmoid_score_above_threshold() {
+    assert!((super::sigmoid(0.0) - 0.5).abs() < 1e-6);
+    assert!(super::sigmoid(-1000.0) >= 0.0 && super::sigmoid(1000.0) <= 1.0);
+
+    let labels: Vec<String> = ["toxic", "insult", "threat"].map(String::from).into();
+    // sigmoid: 2.0 -> 0.88, -1.0 -> 0.27, 0.5 -> 0.62, extra logit 3.0 -> 0.95
+    let picked = super::labels_above(&labels, &[2.0, -1.0, 0.5, 3.0], 0.6);
+    let names: Vec<&str> = picked.iter().map(|(l, _)| l.as_str()).collect();
+    assert_eq!(names, ["toxic", "threat", "unknown"]);
+    assert!((picked[0].1 - 0.8808).abs() < 1e-3);
+
+    assert!(super::labels_above(&labels, &[-5.0, -5.0, -5.0], 0.5).is_empty());
+}
+
+#[test]
+fn multi_label_flag_comes_from_problem_type() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    assert!(!super::multi_label_from_config(dir.path()));
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"problem_type": "multi_label_classification"}"#,
+    )?;
+    assert!(super::multi_label_from_config(dir.path()));
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"problem_type": "single_label_classification"}"#,
+    )?;
+    assert!(!super::multi_label_from_config(dir.path()));
+    Ok(())
+}
+
+#[test]
+fn predict_all_covers_every_label() -> Result<()> {
+    let model_path = "models/distilbert-sst2/model.onnx";
+    if should_skip(model_path) {
+        return Ok(());
+    }
+
+    let model = AIModel::new(model_path)?;
+    let all = model.predict_all("This is wonderful!")?;
+    assert_eq!(
+        all.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>(),
+        ModelKind::SST2.labels()
+    );
+    let sum: f32 = all.iter().map(|(_, p)| p).sum();
+    assert!((sum - 1.0).abs() < 1e-4, "sum was {sum}");
+
+    let (label, score) = model.predict_with_score("This is wonderful!")?;
+    let best = all.iter().find(|(l, _)| *l == label).expect("label listed");
+    assert_eq!(best.1, score);
+    Ok(())
+}
+
+#[test]
+fn predict_batch_matches_one_call_per_text() -> Result<()> {
+    let model_path = "models/distilbert-sst2/model.onnx";
+    if should_skip(model_path) {
+        return Ok(());
+    }
+
+    let model = AIModel::new(model_path)?;
+    let texts = [
+        "This is wonderful!",
+        "I hate this.",
+        "",
+        "It was fine, I guess.",
+    ];
+    let batch = model.predict_batch(&texts)?;
+    assert_eq!(batch.len(), texts.len());
+    for (text, got) in texts.iter().zip(&batch) {
+        let (label, score) = model.predict_with_score(text)?;
+        assert_eq!(got.0, label, "{text:?}");
+        assert!(
+            (got.1 - score).abs() < 1e-5,
+            "{text:?}: {} vs {score}",
+            got.1
+        );
+    }
+    assert!(model.predict_batch(&[])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn batch_rows_encode_like_single_texts() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_fixture_tokenizer(dir.path());
+    let encoder = TextEncoder::load(dir.path(), &ModelKind::SST2)?;
+
+    let texts = ["hello", "world hello", "hello world hello"];
+    let (ids, mask) = encoder.encode_batch(&texts)?;
+    assert_eq!(ids.shape(), &[3, DEFAULT_MAX_LENGTH]);
+    assert_eq!(mask.shape(), &[3, DEFAULT_MAX_LENGTH]);
+    let (ids, mask) = (ids.to_array_view::<i64>()?, mask.to_array_view::<i64>()?);
+    for (row, text) in texts.iter().enumerate() {
+        let (one_ids, one_mask) = encoder.encode_batch(&[text])?;
+        assert_eq!(
+            ids.index_axis(Axis(0), row),
+            one_ids.to_array_view::<i64>()?.index_axis(Axis(0), 0)
+        );
+        assert_eq!(
+            mask.index_axis(Axis(0), row),
+            one_mask.to_array_view::<i64>()?.index_axis(Axis(0), 0)
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn custom_max_length_sets_input_tensor_shape() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_fixture_tokenizer(dir.path());
+
+    let encoder = TextEncoder::load(dir.path(), &ModelKind::SST2)?;
+    let (ids, mask) = encoder.encode_batch(&["hello world"])?;
+    assert_eq!(ids.shape(), &[1, DEFAULT_MAX_LENGTH]);
+    assert_eq!(mask.shape(), &[1, DEFAULT_MAX_LENGTH]);
+
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"max_position_embeddings": 512, "nc_max_length": 16}"#,
+    )?;
+    let encoder = TextEncoder::load(dir.path(), &ModelKind::FactCheck)?;
+    let (ids, mask) = encoder.encode_batch(&["hello world hello world"])?;
+    assert_eq!(ids.shape(), &[1, 16]);
+    assert_eq!(mask.shape(), &[1, 16]);
+    Ok(())
+}
+
+#[test]
+fn max_position_embeddings_only_caps_the_default() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_fixture_tokenizer(dir.path());
+
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"max_position_embeddings": 512}"#,
+    )?;
+    assert_eq!(
+        TextEncoder::load(dir.path(), &ModelKind::SST2)?.max_length,
+        128
+    );
+
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"max_position_embeddings": 64}"#,
+    )?;
+    assert_eq!(
+        TextEncoder::load(dir.path(), &ModelKind::SST2)?.max_length,
+        64
+    );
+    Ok(())
+}
+
+#[test]
+fn labels_json_overrides_builtin_labels() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    assert_eq!(
+        super::load_labels(dir.path(), &ModelKind::SST2)?,
+        vec!["Negative", "Positive"]
+    );
+
+    std::fs::write(dir.path().join("labels.json"), r#"["bad", "meh", "good"]"#)?;
+    assert_eq!(
+        super::load_labels(dir.path(), &ModelKind::SST2)?,
+        vec!["bad", "meh", "good"]
+    );
+
+    std::fs::write(dir.path().join("labels.json"), r#"{"1": "yes", "0": "no"}"#)?;
+    assert_eq!(
+        super::load_labels(dir.path(), &ModelKind::Unknown)?,
+        vec!["no", "yes"]
+    );
+
+    std::fs::write(dir.path().join("labels.json"), r#"{"0": "no", "2": "gap"}"#)?;
+    assert!(super::load_labels(dir.path(), &ModelKind::Unknown).is_err());
+    Ok(())
+}
+
+#[test]
+fn config_id2label_is_used_for_unknown_models_only() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(
+        dir.path().join("config.json"),
+        r#"{"id2label": {"0": "NEGATIVE", "1": "POSITIVE"}}"#,
+    )?;
+    assert_eq!(
+        super::load_labels(dir.path(), &ModelKind::Unknown)?,
+        vec!["NEGATIVE", "POSITIVE"]
+    );
+    assert_eq!(
+        super::load_labels(dir.path(), &ModelKind::SST2)?,
+        vec!["Negative", "Positive"]
+    );
+    Ok(())
+}
+
+#[test]
+fn model_kind_from_id_follows_the_bundled_dirs() {
+    assert_eq!(ModelKind::from_id("sst2"), Some(ModelKind::SST2));
+    assert_eq!(ModelKind::from_id("toxic"), Some(ModelKind::Toxic));
+    assert_eq!(ModelKind::from_id("macro"), Some(ModelKind::MacroIntent));
+    assert_eq!(
+        ModelKind::from_id("intent_stellar"),
+        Some(ModelKind::IntentStellar)
+    );
+    assert_eq!(ModelKind::from_id("nope"), None);
+    assert_eq!(
+        ModelKind::from_path("custom/toxic_v2/model.onnx"),
+        ModelKind::Toxic
+    );
+}
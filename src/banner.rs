@@ -1,12 +1,12 @@
-use std::env;
 use std::io::{self, Write};
 
-fn banner_text() -> String {
-    let no_color = env::var("NO_COLOR").is_ok();
-    let (c, r) = if no_color {
-        ("", "")
-    } else {
+use crate::color::{self, Stream};
+
+fn banner_text(stream: Stream) -> String {
+    let (c, r) = if color::enabled(stream) {
         ("\x1b[96m", "\x1b[0m")
+    } else {
+        ("", "")
     };
     let logo = concat!(
         " _   _                      _____ _           _          _____  _____  _      \n",
@@ -20,9 +20,9 @@ fn banner_text() -> String {
 }
 
 pub fn print_banner() {
-    let _ = io::stdout().write_all(banner_text().as_bytes());
+    let _ = io::stdout().write_all(banner_text(Stream::Stdout).as_bytes());
 }
 
 pub fn print_banner_stderr() {
-    let _ = io::stderr().write_all(banner_text().as_bytes());
+    let _ = io::stderr().write_all(banner_text(Stream::Stderr).as_bytes());
 }
@@ -1,19 +1,27 @@
 use std::{
+    collections::VecDeque,
     env, fs,
-    net::SocketAddr,
+    future::IntoFuture,
+    net::{IpAddr, SocketAddr},
     panic::{catch_unwind, AssertUnwindSafe},
-    sync::{Arc, Mutex, OnceLock},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Instant,
 };
 
 use axum::{
-    extract::State,
+    extract::{ConnectInfo, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use neurochain::{
     actions::{validate_enforced_plan, validate_plan, Action, ActionPlan, Allowlist},
+    ai::model::{self, AIModel, MODEL_IDS},
     banner, engine,
     intent_stellar::{
         build_action_plan as build_intent_action_plan, classify as classify_intent_stellar,
@@ -32,7 +40,7 @@ use neurochain::{
 };
 use serde::{Deserialize, Serialize};
 use tokio::{
-    sync::Semaphore,
+    sync::{watch, OwnedSemaphorePermit, Semaphore},
     task,
     time::{timeout, Duration},
 };
@@ -41,8 +49,27 @@ use tower_http::cors::{Any, CorsLayer};
 #[derive(Clone)]
 struct AppState {
     inference_sem: Arc<Semaphore>,
+    /// Permits `inference_sem` was created with (`NC_MAX_INFER`).
+    inference_permits: usize,
+    /// `/api/analyze` requests received since start, including rejected ones.
+    requests_total: Arc<AtomicUsize>,
     x402_stellar: Arc<Mutex<Box<dyn X402ChallengeStore + Send>>>,
     x402_payment_verifier: Arc<dyn X402PaymentVerifier + Send + Sync>,
+    recent: Arc<Mutex<VecDeque<String>>>,
+    recent_cap: usize,
+}
+
+impl AppState {
+    /// Appends output lines to the live-tail buffer, dropping the oldest past `recent_cap`.
+    fn push_recent(&self, output: &str) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        for line in output.lines() {
+            recent.push_back(line.to_string());
+        }
+        while recent.len() > self.recent_cap {
+            recent.pop_front();
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -60,6 +87,66 @@ struct AnalyzeResp {
     ok: bool,
     output: String,
     logs: Vec<String>,
+    /// Model path the script actually ran with (injected or from its own `AI:` line).
+    model_path: Option<String>,
+}
+
+const NEUROCHAIN_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Serialize)]
+struct HealthResp {
+    status: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct ModelInfo {
+    id: &'static str,
+    aliases: &'static [&'static str],
+    path: String,
+    available: bool,
+}
+
+#[derive(Serialize)]
+struct ModelsResp {
+    ok: bool,
+    models: Vec<ModelInfo>,
+}
+
+#[derive(Serialize)]
+struct RecentResp {
+    ok: bool,
+    lines: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+struct MetricsResp {
+    ok: bool,
+    inference_permits_available: usize,
+    inference_permits_total: usize,
+    requests_total: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct ClassifyReq {
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// How many labels `/api/classify` returns in `topk`.
+const CLASSIFY_TOPK: usize = 3;
+
+#[derive(Serialize, Default)]
+struct ClassifyResp {
+    ok: bool,
+    label: String,
+    score: f32,
+    /// `[label, score]` pairs, best first.
+    topk: Vec<(String, f32)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -110,6 +197,13 @@ fn required_api_key() -> Option<&'static str> {
         .as_deref()
 }
 
+/// True when no `NC_API_KEY` is set, or `headers` carry it.
+fn authorized(headers: &HeaderMap) -> bool {
+    required_api_key().is_none_or(|required| {
+        provided_api_key(headers).is_some_and(|got| secure_eq(got, required))
+    })
+}
+
 fn provided_api_key(headers: &HeaderMap) -> Option<&str> {
     let from_x_api_key = headers
         .get("x-api-key")
@@ -149,24 +243,110 @@ fn is_false(value: &bool) -> bool {
     !*value
 }
 
-fn models_base() -> String {
-    env::var("NC_MODELS_DIR").unwrap_or_else(|_| "/opt/neurochain/models".to_string())
+/// Upper bound on one `/api/analyze` run (`NC_ANALYZE_TIMEOUT_MS`, default 5000).
+fn analyze_timeout() -> Duration {
+    let ms = env::var("NC_ANALYZE_TIMEOUT_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(5000);
+    Duration::from_millis(ms)
 }
 
-fn resolve_model_path(id: &str) -> Option<String> {
-    let base = models_base();
-    let path = match id {
-        "sst2" => format!("{base}/distilbert-sst2/model.onnx"),
-        "factcheck" => format!("{base}/factcheck/model.onnx"),
-        "intent" => format!("{base}/intent/model.onnx"),
-        "intent_stellar" | "stellar_intent" => format!("{base}/intent_stellar/model.onnx"),
-        "toxic" => format!("{base}/toxic_quantized/model.onnx"),
-        "macro" | "intent_macro" | "macro_intent" | "gpt2" | "generator" => {
-            format!("{base}/intent_macro/model.onnx")
-        }
-        _ => return None,
+/// `NC_NO_OUTPUT_SENTINEL`: `output` for a script that printed nothing. Set it empty to return
+/// `""`; unset keeps [`engine::NO_OUTPUT_SENTINEL`].
+fn no_output_sentinel() -> String {
+    env::var("NC_NO_OUTPUT_SENTINEL").unwrap_or_else(|_| engine::NO_OUTPUT_SENTINEL.to_string())
+}
+
+/// `NC_REQUEST_LOG=1`: add a `request: ip=... model=... status=... latency_ms=...` line to the
+/// `/api/analyze` logs and stderr.
+fn request_log_enabled() -> bool {
+    parse_bool_value(&env::var("NC_REQUEST_LOG").unwrap_or_default()).unwrap_or(false)
+}
+
+/// `NC_TRUST_PROXY=1`: the server sits behind a reverse proxy that sets `X-Forwarded-For`.
+fn trust_proxy_enabled() -> bool {
+    parse_bool_value(&env::var("NC_TRUST_PROXY").unwrap_or_default()).unwrap_or(false)
+}
+
+/// Caller address for logs: the first `X-Forwarded-For` hop when `NC_TRUST_PROXY` is on, else the
+/// peer (any client can send the header, so it is ignored unless a proxy sets it).
+fn client_ip(headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    if !trust_proxy_enabled() {
+        return peer.ip();
+    }
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or_else(|| peer.ip())
+}
+
+/// First ID of the `MODEL_IDS` entry `id` belongs to, so aliases log the same; `-` if none given.
+fn canonical_model_id(id: &str) -> &str {
+    let id = id.trim();
+    if id.is_empty() {
+        return "-";
+    }
+    MODEL_IDS
+        .iter()
+        .find(|(ids, _)| ids.contains(&id))
+        .map_or(id, |(ids, _)| ids[0])
+}
+
+/// `NC_INFER_THREADS`: size of the blocking pool that runs `/api/analyze` (and the rayon
+/// pool used by the tokenizer). Unset or `0` keeps the runtime defaults.
+fn infer_threads() -> Option<usize> {
+    env::var("NC_INFER_THREADS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&n| n > 0)
+}
+
+/// `NC_SHUTDOWN_GRACE_SECS`: how long in-flight requests may run after SIGINT/SIGTERM
+/// (default 10s).
+fn shutdown_grace() -> Duration {
+    Duration::from_secs(
+        env::var("NC_SHUTDOWN_GRACE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10),
+    )
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on unix, and sends the end of the grace period to `stop` so
+/// the grace timer can begin.
+async fn shutdown_signal(stop: watch::Sender<Option<Instant>>) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl-C handler");
     };
-    Some(path)
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    println!("Shutting down...");
+    let _ = stop.send(Some(Instant::now() + shutdown_grace()));
+}
+
+fn model_path_for_dir(dir: &str) -> String {
+    format!("{}/{dir}/model.onnx", model::models_root())
+}
+
+fn resolve_model_path(id: &str) -> Option<String> {
+    model::resolve_model_path(&model::models_root(), id)
 }
 
 fn resolve_stellar_intent_model_path(
@@ -327,8 +507,33 @@ fn normalize(s: &str) -> String {
         .join("\n")
 }
 
-#[tokio::main]
-async fn main() {
+fn main() {
+    // Deployed layout; `NC_MODELS_DIR` still wins. Scripts' `# @model` IDs resolve here too.
+    model::set_default_models_root("/opt/neurochain/models");
+    let infer_threads = infer_threads();
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    runtime.enable_all();
+    if let Some(n) = infer_threads {
+        // Must happen before the tokenizer first touches rayon's global pool.
+        if env::var_os("RAYON_NUM_THREADS").is_none() {
+            env::set_var("RAYON_NUM_THREADS", n.to_string());
+        }
+        // A run keeps its inference permit while it holds one of these threads (timed-out runs
+        // stop at their next loop iteration), so at most `NC_MAX_INFER` of them are busy.
+        runtime.max_blocking_threads(n);
+    }
+    let runtime = runtime.build().expect("failed to build tokio runtime");
+    let grace_end = runtime.block_on(serve(infer_threads));
+    // Analyses that outlived their request (timed out) still hold blocking threads. They get
+    // what is left of the one grace period, not a second one.
+    let left = grace_end.map_or(Duration::ZERO, |end| {
+        end.saturating_duration_since(Instant::now())
+    });
+    runtime.shutdown_timeout(left);
+}
+
+/// Runs the server until a shutdown signal, and returns when its grace period ends.
+async fn serve(infer_threads: Option<usize>) -> Option<Instant> {
     banner::print_banner();
     std::panic::set_hook(Box::new(|info| {
         eprintln!("PANIC: {info}");
@@ -341,15 +546,27 @@ async fn main() {
         .ok()
         .and_then(|s| s.parse().ok())
         .unwrap_or(2);
+    let recent_cap: usize = env::var("NC_RECENT_LINES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(200);
 
     let state = Arc::new(AppState {
         inference_sem: Arc::new(Semaphore::new(max_infer)),
+        inference_permits: max_infer,
+        requests_total: Arc::new(AtomicUsize::new(0)),
         x402_stellar: Arc::new(Mutex::new(build_x402_challenge_store())),
         x402_payment_verifier: Arc::from(build_x402_payment_verifier()),
+        recent: Arc::new(Mutex::new(VecDeque::with_capacity(recent_cap))),
+        recent_cap,
     });
 
     let api = Router::new()
         .route("/analyze", post(api_analyze))
+        .route("/classify", post(api_classify))
+        .route("/models", get(api_models))
+        .route("/recent", get(api_recent))
+        .route("/metrics", get(api_metrics))
         .route("/stellar/intent-plan", post(api_stellar_intent_plan))
         .route(
             "/stellar/zk-attestation/view",
@@ -361,12 +578,15 @@ async fn main() {
         )
         .with_state(state);
 
-    let app = Router::new().nest("/api", api).layer(
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any),
-    );
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .nest("/api", api)
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        );
 
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port: u16 = env::var("PORT")
@@ -376,6 +596,9 @@ async fn main() {
     let addr: SocketAddr = format!("{host}:{port}").parse().expect("Invalid HOST/PORT");
 
     println!("NeuroChain API listening on http://{addr}");
+    if let Some(n) = infer_threads {
+        println!("Inference threads: {n}");
+    }
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
@@ -387,37 +610,85 @@ async fn main() {
             std::process::exit(1);
         });
 
-    if let Err(e) = axum::serve(listener, app).await {
-        eprintln!("ERROR: server error: {e}");
-        std::process::exit(1);
+    let (stop, grace_end) = watch::channel(None);
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(stop))
+    .into_future();
+    let mut wait_end = grace_end.clone();
+    let grace_elapsed = async move {
+        match wait_end.wait_for(Option::is_some).await.map(|end| *end) {
+            Ok(Some(end)) => tokio::time::sleep_until(end.into()).await,
+            _ => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        res = server => {
+            if let Err(e) = res {
+                eprintln!("ERROR: server error: {e}");
+                std::process::exit(1);
+            }
+        }
+        _ = grace_elapsed => {
+            eprintln!(
+                "WARN: requests still open after {}s; exiting anyway",
+                shutdown_grace().as_secs()
+            );
+        }
     }
+    let end = *grace_end.borrow();
+    end
 }
 
 async fn api_analyze(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(req): Json<AnalyzeReq>,
 ) -> impl IntoResponse {
+    state.requests_total.fetch_add(1, Ordering::Relaxed);
+    if !request_log_enabled() {
+        return run_analyze(state, headers, req).await;
+    }
+
+    let ip = client_ip(&headers, peer);
+    let model = canonical_model_id(&req.model).to_string();
+    let started = Instant::now();
+    let (status, Json(mut resp)) = run_analyze(state, headers, req).await;
+    let line = format!(
+        "request: ip={ip} model={model} status={} latency_ms={:.1}",
+        status.as_u16(),
+        started.elapsed().as_secs_f64() * 1000.0
+    );
+    eprintln!("{line}");
+    resp.logs.push(line);
+    (status, Json(resp))
+}
+
+async fn run_analyze(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    req: AnalyzeReq,
+) -> (StatusCode, Json<AnalyzeResp>) {
     let mut logs: Vec<String> = Vec::new();
     if !req.model.is_empty() {
         logs.push(format!("model={}", req.model));
     }
 
-    if let Some(required) = required_api_key() {
-        let ok = provided_api_key(&headers)
-            .map(|got| secure_eq(got, required))
-            .unwrap_or(false);
-        if !ok {
-            logs.push("auth: missing or invalid api key".into());
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(AnalyzeResp {
-                    ok: false,
-                    output: "ERROR: unauthorized".into(),
-                    logs,
-                }),
-            );
-        }
+    if !authorized(&headers) {
+        logs.push("auth: missing or invalid api key".into());
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(AnalyzeResp {
+                ok: false,
+                output: "ERROR: unauthorized".into(),
+                logs,
+                model_path: None,
+            }),
+        );
     }
 
     let mut code = req.code.or(req.content).unwrap_or_default();
@@ -429,6 +700,7 @@ async fn api_analyze(
                 ok: false,
                 output: "ERROR: empty input".into(),
                 logs,
+                model_path: None,
             }),
         );
     }
@@ -445,40 +717,64 @@ async fn api_analyze(
 
     let code = normalize(&code);
 
-    let permit = match state.inference_sem.clone().try_acquire_owned() {
-        Ok(p) => p,
-        Err(_) => {
-            let maybe = timeout(
-                Duration::from_millis(50),
-                state.inference_sem.clone().acquire_owned(),
-            )
-            .await;
-            match maybe {
-                Ok(Ok(p)) => p,
-                _ => {
-                    logs.push("busy: inference slots full".into());
-                    return (
-                        StatusCode::SERVICE_UNAVAILABLE,
-                        Json(AnalyzeResp {
-                            ok: false,
-                            output: "BUSY: inference slots full; please retry shortly.".into(),
-                            logs,
-                        }),
-                    );
-                }
-            }
-        }
+    let Some(permit) = acquire_inference(&state).await else {
+        logs.push("busy: inference slots full".into());
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(AnalyzeResp {
+                ok: false,
+                output: "BUSY: inference slots full; please retry shortly.".into(),
+                logs,
+                model_path: None,
+            }),
+        );
     };
 
-    let task_res = task::spawn_blocking(move || {
+    let sentinel = no_output_sentinel();
+    let limit = analyze_timeout();
+    let deadline = Instant::now() + limit;
+    let task = task::spawn_blocking(move || {
+        // The slot goes back when the run really ends, not when the request gives up on it, so
+        // no more than `NC_MAX_INFER` scripts ever run at once.
+        let _permit = permit;
         catch_unwind(AssertUnwindSafe(|| {
             let mut interpreter = interpreter::Interpreter::new();
-            engine::analyze(&code, &mut interpreter)
+            interpreter.set_deadline(deadline);
+            // Keep whether the script printed anything: the sentinel answer stays out of the
+            // live tail, while real output that happens to read the same goes in.
+            let res = engine::analyze_with_sentinel(&code, &mut interpreter, "").map(|out| {
+                if out.trim().is_empty() {
+                    (interpreter.plain_text(&sentinel), false)
+                } else {
+                    (out, true)
+                }
+            });
+            (res, interpreter.model_path().map(str::to_string))
         }))
-    })
-    .await;
+    });
 
-    drop(permit);
+    let timed_out = |mut logs: Vec<String>| {
+        logs.push("busy: analyze timed out".into());
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(AnalyzeResp {
+                ok: false,
+                output: format!(
+                    "TIMEOUT: script did not finish within {} ms.",
+                    limit.as_millis()
+                ),
+                logs,
+                model_path: None,
+            }),
+        )
+    };
+    let task_res = match timeout(limit, task).await {
+        Ok(res) => res,
+        // The blocking thread can't be cancelled from here. The interpreter stops at its next
+        // loop iteration past the deadline (a long model call still runs to the end) and only
+        // then releases the slot.
+        Err(_) => return timed_out(logs),
+    };
 
     let res = match task_res {
         Ok(inner) => inner,
@@ -490,48 +786,222 @@ async fn api_analyze(
                     ok: false,
                     output: "ERROR: internal join error in analyze()".into(),
                     logs,
+                    model_path: None,
                 }),
             );
         }
     };
 
     match res {
-        Ok(Ok(out)) => (
-            StatusCode::OK,
-            Json(AnalyzeResp {
-                ok: true,
-                output: out,
-                logs,
-            }),
-        ),
-        Ok(Err(e)) => (
-            StatusCode::OK,
+        // The interpreter hit its deadline just before the timer above fired.
+        Ok((Err(_), _)) if Instant::now() >= deadline => timed_out(logs),
+        Ok((Ok((out, printed)), model_path)) => {
+            if printed {
+                state.push_recent(&out);
+            }
+            (
+                StatusCode::OK,
+                Json(AnalyzeResp {
+                    ok: true,
+                    output: out,
+                    logs,
+                    model_path,
+                }),
+            )
+        }
+        Ok((Err(e), model_path)) => (
+            // A malformed script is the client's fault; a script that fails while running is a
+            // normal `ok:false` result.
+            if e.is_syntax() {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::OK
+            },
             Json(AnalyzeResp {
                 ok: false,
                 output: format!("ERROR: {e}"),
                 logs,
+                model_path,
             }),
         ),
         Err(panic) => {
-            let msg = if let Some(s) = panic.downcast_ref::<&str>() {
-                s.to_string()
-            } else if let Some(s) = panic.downcast_ref::<String>() {
-                s.clone()
-            } else {
-                "internal panic in analyze()".to_string()
-            };
+            let msg =
+                engine::panic_message(panic.as_ref()).unwrap_or("internal panic in analyze()");
             (
                 StatusCode::OK,
                 Json(AnalyzeResp {
                     ok: false,
                     output: format!("ERROR: {msg}"),
                     logs,
+                    model_path: None,
+                }),
+            )
+        }
+    }
+}
+
+/// An inference slot, waiting at most 50 ms for one to free up; `None` when all stay busy.
+async fn acquire_inference(state: &AppState) -> Option<OwnedSemaphorePermit> {
+    if let Ok(permit) = state.inference_sem.clone().try_acquire_owned() {
+        return Some(permit);
+    }
+    timeout(
+        Duration::from_millis(50),
+        state.inference_sem.clone().acquire_owned(),
+    )
+    .await
+    .ok()?
+    .ok()
+}
+
+/// Predicted label, score and top labels for `text` with the `model` ID. Nothing is generated
+/// or run; the call takes an inference slot and the `/api/analyze` time limit like a script.
+async fn api_classify(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<ClassifyReq>,
+) -> (StatusCode, Json<ClassifyResp>) {
+    let fail = |status: StatusCode, error: String| {
+        (
+            status,
+            Json(ClassifyResp {
+                error: Some(error),
+                ..ClassifyResp::default()
+            }),
+        )
+    };
+
+    if !authorized(&headers) {
+        return fail(StatusCode::UNAUTHORIZED, "unauthorized".into());
+    }
+    if req.text.trim().is_empty() {
+        return fail(StatusCode::BAD_REQUEST, "empty text".into());
+    }
+    let Some(path) = resolve_model_path(&req.model) else {
+        return fail(
+            StatusCode::BAD_REQUEST,
+            format!("unknown model id '{}'", req.model),
+        );
+    };
+    let Some(permit) = acquire_inference(&state).await else {
+        return fail(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "BUSY: inference slots full; please retry shortly.".into(),
+        );
+    };
+
+    let text = req.text;
+    let task = task::spawn_blocking(move || {
+        // Held until the prediction really ends, also when the request timed out first.
+        let _permit = permit;
+        AIModel::load_cached(&path).and_then(|m| m.predict_topk(&text, CLASSIFY_TOPK))
+    });
+    let limit = analyze_timeout();
+    let res = timeout(limit, task).await;
+
+    match res {
+        Err(_) => fail(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!(
+                "TIMEOUT: classification did not finish within {} ms.",
+                limit.as_millis()
+            ),
+        ),
+        Ok(Err(e)) => fail(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("join error: {e}"),
+        ),
+        Ok(Ok(Err(e))) => fail(StatusCode::OK, format!("model error: {e}")),
+        Ok(Ok(Ok(topk))) => {
+            let (label, score) = topk.first().cloned().unwrap_or_default();
+            (
+                StatusCode::OK,
+                Json(ClassifyResp {
+                    ok: true,
+                    label,
+                    score,
+                    topk,
+                    error: None,
                 }),
             )
         }
     }
 }
 
+/// Liveness probe: no API key, no inference permit.
+async fn healthz() -> Json<HealthResp> {
+    Json(HealthResp {
+        status: "ok",
+        version: NEUROCHAIN_VERSION,
+    })
+}
+
+async fn api_models(headers: HeaderMap) -> impl IntoResponse {
+    if !authorized(&headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ModelsResp {
+                ok: false,
+                models: Vec::new(),
+            }),
+        );
+    }
+
+    let models = MODEL_IDS
+        .iter()
+        .map(|(ids, dir)| {
+            let path = model_path_for_dir(dir);
+            ModelInfo {
+                id: ids[0],
+                aliases: &ids[1..],
+                available: Path::new(&path).exists(),
+                path,
+            }
+        })
+        .collect();
+    (StatusCode::OK, Json(ModelsResp { ok: true, models }))
+}
+
+/// Last `NC_RECENT_LINES` output lines across all `/api/analyze` runs, oldest first.
+async fn api_recent(State(state): State<Arc<AppState>>, headers: HeaderMap) -> impl IntoResponse {
+    if !authorized(&headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(RecentResp {
+                ok: false,
+                lines: Vec::new(),
+            }),
+        );
+    }
+
+    let lines = state
+        .recent
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect();
+    (StatusCode::OK, Json(RecentResp { ok: true, lines }))
+}
+
+/// Inference load: free and total permits plus the `/api/analyze` request count. Reads only
+/// atomics, so it never waits behind a running analysis.
+async fn api_metrics(State(state): State<Arc<AppState>>, headers: HeaderMap) -> impl IntoResponse {
+    if !authorized(&headers) {
+        return (StatusCode::UNAUTHORIZED, Json(MetricsResp::default()));
+    }
+
+    (
+        StatusCode::OK,
+        Json(MetricsResp {
+            ok: true,
+            inference_permits_available: state.inference_sem.available_permits(),
+            inference_permits_total: state.inference_permits,
+            requests_total: state.requests_total.load(Ordering::Relaxed),
+        }),
+    )
+}
+
 async fn api_stellar_intent_plan(
     _state: State<Arc<AppState>>,
     headers: HeaderMap,
@@ -539,25 +1009,20 @@ async fn api_stellar_intent_plan(
 ) -> impl IntoResponse {
     let mut logs: Vec<String> = Vec::new();
 
-    if let Some(required) = required_api_key() {
-        let ok = provided_api_key(&headers)
-            .map(|got| secure_eq(got, required))
-            .unwrap_or(false);
-        if !ok {
-            logs.push("auth: missing or invalid api key".into());
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(StellarIntentPlanResp {
-                    ok: false,
-                    blocked: true,
-                    exit_code: Some(1),
-                    error: Some("unauthorized".to_string()),
-                    requires_approval: false,
-                    plan: ActionPlan::default(),
-                    logs,
-                }),
-            );
-        }
+    if !authorized(&headers) {
+        logs.push("auth: missing or invalid api key".into());
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(StellarIntentPlanResp {
+                ok: false,
+                blocked: true,
+                exit_code: Some(1),
+                error: Some("unauthorized".to_string()),
+                requires_approval: false,
+                plan: ActionPlan::default(),
+                logs,
+            }),
+        );
     }
 
     build_stellar_intent_plan_response(req, logs)
@@ -568,18 +1033,13 @@ async fn api_stellar_zk_attestation_view(
     Json(req): Json<ZkAttestationViewRequest>,
 ) -> Response {
     let mut logs = vec!["zk_attestation: read-only public artifact view".to_string()];
-    if let Some(required) = required_api_key() {
-        let ok = provided_api_key(&headers)
-            .map(|got| secure_eq(got, required))
-            .unwrap_or(false);
-        if !ok {
-            logs.push("auth: missing or invalid api key".to_string());
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ZkAttestationViewResponse::failure("unauthorized", logs)),
-            )
-                .into_response();
-        }
+    if !authorized(&headers) {
+        logs.push("auth: missing or invalid api key".to_string());
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ZkAttestationViewResponse::failure("unauthorized", logs)),
+        )
+            .into_response();
     }
 
     match inspect_zk_attestation(req) {
@@ -613,20 +1073,15 @@ async fn api_x402_stellar_intent_plan(
         state.x402_payment_verifier.boundary_kind()
     ));
 
-    if let Some(required) = required_api_key() {
-        let ok = provided_api_key(&headers)
-            .map(|got| secure_eq(got, required))
-            .unwrap_or(false);
-        if !ok {
-            logs.push("auth: missing or invalid api key".to_string());
-            return x402_error_response(
-                StatusCode::UNAUTHORIZED,
-                "unauthorized",
-                "unauthorized",
-                X402PaymentContext::default(),
-                logs,
-            );
-        }
+    if !authorized(&headers) {
+        logs.push("auth: missing or invalid api key".to_string());
+        return x402_error_response(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "unauthorized",
+            X402PaymentContext::default(),
+            logs,
+        );
     }
 
     let Some(signature) = x402_payment_signature(&headers) else {
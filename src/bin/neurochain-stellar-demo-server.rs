@@ -21,6 +21,7 @@ use axum::{
 };
 use neurochain::{
     actions::{validate_plan, Action, ActionPlan, Allowlist},
+    ai::model,
     banner, engine,
     intent_stellar::{
         build_action_plan as build_intent_action_plan, classify as classify_intent_stellar,
@@ -235,24 +236,8 @@ fn parse_bool_value(raw: &str) -> Option<bool> {
     }
 }
 
-fn models_base() -> String {
-    env::var("NC_MODELS_DIR").unwrap_or_else(|_| "/opt/neurochain/models".to_string())
-}
-
 fn resolve_model_path(id: &str) -> Option<String> {
-    let base = models_base();
-    let path = match id {
-        "sst2" => format!("{base}/distilbert-sst2/model.onnx"),
-        "factcheck" => format!("{base}/factcheck/model.onnx"),
-        "intent" => format!("{base}/intent/model.onnx"),
-        "intent_stellar" | "stellar_intent" => format!("{base}/intent_stellar/model.onnx"),
-        "toxic" => format!("{base}/toxic_quantized/model.onnx"),
-        "macro" | "intent_macro" | "macro_intent" | "gpt2" | "generator" => {
-            format!("{base}/intent_macro/model.onnx")
-        }
-        _ => return None,
-    };
-    Some(path)
+    model::resolve_model_path(&model::models_root(), id)
 }
 
 fn allowlist_enforced(override_value: Option<bool>) -> bool {
@@ -807,7 +792,7 @@ fn default_repl_bin_path() -> String {
 }
 
 fn ws_text_message(text: impl Into<String>) -> Message {
-    Message::Text(text.into().into())
+    Message::Text(text.into())
 }
 
 fn env_u64(name: &str, default: u64) -> u64 {
@@ -869,7 +854,7 @@ fn parse_value_after_colon(input: &str) -> Option<String> {
     Some(value)
 }
 
-fn parse_value_after_prefix<'a>(input: &'a str, prefix: &str) -> Option<String> {
+fn parse_value_after_prefix(input: &str, prefix: &str) -> Option<String> {
     let lower = input.to_ascii_lowercase();
     if !lower.starts_with(prefix) {
         return None;
@@ -1495,13 +1480,8 @@ async fn api_analyze(
             }),
         ),
         Err(panic) => {
-            let msg = if let Some(s) = panic.downcast_ref::<&str>() {
-                s.to_string()
-            } else if let Some(s) = panic.downcast_ref::<String>() {
-                s.clone()
-            } else {
-                "internal panic in analyze()".to_string()
-            };
+            let msg =
+                engine::panic_message(panic.as_ref()).unwrap_or("internal panic in analyze()");
             (
                 StatusCode::OK,
                 Json(AnalyzeResp {
@@ -1697,6 +1677,8 @@ async fn api_stellar_intent_plan(
 
 #[tokio::main]
 async fn main() {
+    // Deployed layout; `NC_MODELS_DIR` still wins. Scripts' `# @model` IDs resolve here too.
+    model::set_default_models_root("/opt/neurochain/models");
     banner::print_banner();
 
     if required_api_key().is_none() {
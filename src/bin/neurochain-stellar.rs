@@ -12,6 +12,7 @@ use neurochain::actions::{
 };
 use neurochain::ai::model::AIModel;
 use neurochain::banner;
+use neurochain::color::{self, Stream};
 use neurochain::help_text::neurochain_language_help;
 use neurochain::intent_stellar::{
     build_action_plan as build_intent_action_plan, classify as classify_intent_stellar,
@@ -4212,7 +4213,7 @@ fn divider_line() -> String {
 fn print_repl_divider() {
     // Keep a small right margin so the divider does not feel edge-to-edge.
     let line = divider_line();
-    if env::var("NO_COLOR").is_ok() {
+    if !color::enabled(Stream::Stdout) {
         println!("{line}");
     } else {
         println!("\x1b[94m{line}\x1b[0m");
@@ -4221,7 +4222,7 @@ fn print_repl_divider() {
 
 fn print_script_divider_stderr() {
     let line = divider_line();
-    if env::var("NO_COLOR").is_ok() {
+    if !color::enabled(Stream::Stderr) {
         eprintln!("{line}");
     } else {
         eprintln!("\x1b[94m{line}\x1b[0m");
@@ -4315,7 +4316,7 @@ fn print_script_setup(
 }
 
 fn print_repl_hint_line() {
-    if env::var("NO_COLOR").is_ok() {
+    if !color::enabled(Stream::Stdout) {
         println!("Type `help` for quick start, `help all` for full command list, `exit` to quit.");
     } else {
         let green = "\x1b[92m";
@@ -0,0 +1,86 @@
+//! When to write ANSI colours (banner, REPL dividers and hints).
+//!
+//! First match wins: `--color` ([`set_choice`]), `NC_COLOR`, `NO_COLOR` (never),
+//! `CLICOLOR_FORCE` (always), otherwise colour only if the stream is a terminal.
+
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    Always,
+    Never,
+    Auto,
+}
+
+impl ColorChoice {
+    /// `always` / `never` / `auto`, case-insensitive.
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            "auto" => Some(ColorChoice::Auto),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+static CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Records the command-line choice; it beats every environment variable. Only the first call
+/// counts.
+pub fn set_choice(choice: ColorChoice) {
+    let _ = CHOICE.set(choice);
+}
+
+/// The effective choice before tty detection.
+pub fn choice() -> ColorChoice {
+    if let Some(choice) = CHOICE.get() {
+        return *choice;
+    }
+    if let Some(choice) = env::var("NC_COLOR")
+        .ok()
+        .and_then(|v| ColorChoice::parse(&v))
+    {
+        return choice;
+    }
+    if env::var_os("NO_COLOR").is_some() {
+        return ColorChoice::Never;
+    }
+    if env::var("CLICOLOR_FORCE").is_ok_and(|v| !v.is_empty() && v != "0") {
+        return ColorChoice::Always;
+    }
+    ColorChoice::Auto
+}
+
+/// Whether output written to `stream` should carry ANSI colours.
+pub fn enabled(stream: Stream) -> bool {
+    match choice() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => match stream {
+            Stream::Stdout => io::stdout().is_terminal(),
+            Stream::Stderr => io::stderr().is_terminal(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColorChoice;
+
+    #[test]
+    fn parses_the_three_choices() {
+        assert_eq!(ColorChoice::parse("always"), Some(ColorChoice::Always));
+        assert_eq!(ColorChoice::parse(" Never "), Some(ColorChoice::Never));
+        assert_eq!(ColorChoice::parse("AUTO"), Some(ColorChoice::Auto));
+        assert_eq!(ColorChoice::parse("sometimes"), None);
+    }
+}
@@ -1,46 +1,261 @@
+use std::any::Any;
+use std::fmt;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
 use crate::interpreter::Interpreter;
-use crate::lexer::tokenize;
-use crate::parser::parse;
+use crate::lexer::tokenize_with_lines;
+use crate::parser::{first_error, parse, warnings, ParseWarning};
+
+/// What `analyze` returns when a script ran fine but printed nothing.
+pub const NO_OUTPUT_SENTINEL: &str = "Execution succeeded.";
+
+/// Which stage of `analyze` failed. `Display` is the bare message, same text as before.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnalyzeError {
+    /// The lexer rejected the source (e.g. a missing quote).
+    Lex(String),
+    /// Tokens that don't form a valid statement (e.g. `set x = 2 3`).
+    Parse(String),
+    /// The script started but stopped with an error (e.g. a runaway loop).
+    Runtime(String),
+}
+
+impl AnalyzeError {
+    /// True for lex/parse errors: the script itself is malformed and nothing ran.
+    pub fn is_syntax(&self) -> bool {
+        matches!(self, AnalyzeError::Lex(_) | AnalyzeError::Parse(_))
+    }
+}
+
+impl fmt::Display for AnalyzeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzeError::Lex(msg) | AnalyzeError::Parse(msg) | AnalyzeError::Runtime(msg) => {
+                f.write_str(msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalyzeError {}
+
+/// The message of a caught panic, when it carried one (`panic!("...")` or `expect`).
+pub fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
 
 /// Lexer → Parser → Interpreter – one block at a time.
+///
+/// Blank lines end a block, unless the next line still belongs to the open statement:
+/// an indented body line or an `elif`/`else` branch. With `--continue-on-error` every block
+/// runs, and skipped statements still make the result an `Err` at the end.
 pub fn analyze_blocks(input: &str, interpreter: &mut Interpreter) -> Result<(), String> {
+    let res = split_blocks(input)
+        .iter()
+        .try_for_each(|(line, block)| interpreter.run_source_at(block, *line))
+        .map_err(|err| err.to_string())
+        .and_then(|()| interpreter.take_skipped());
+    // A `neuro_raw` line still open at the end (or at an error) is ended here.
+    interpreter.end_partial_line();
+    res
+}
+
+/// Lexes and parses every block the way `analyze_blocks` would, without running anything:
+/// no models are loaded and nothing is printed. Returns the first error, or the warnings of
+/// every block (empty blocks, empty prompts) when there is none.
+pub fn check_blocks(input: &str) -> Result<Vec<ParseWarning>, String> {
+    let mut found = Vec::new();
+    for (line, block) in split_blocks(input) {
+        let tokens = tokenize_with_lines(&block, line)?;
+        found.extend(warnings(&tokens));
+        let ast = parse(tokens);
+        if let Some(err) = first_error(&ast) {
+            return Err(err.to_string());
+        }
+    }
+    Ok(found)
+}
+
+/// Each block with the (1-based) script line it starts on. Blank lines inside a block are
+/// kept, so line `n` of a block is line `start + n - 1` of the script.
+fn split_blocks(input: &str) -> Vec<(usize, String)> {
+    let mut blocks = Vec::new();
     let mut current_block = String::new();
+    let mut start = 1;
+    let lines: Vec<&str> = input.lines().collect();
+
+    // Inside an open `"""` string, blank lines are part of the text.
+    let mut in_triple = false;
 
-    for line in input.lines() {
-        if line.trim().is_empty() {
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() && !in_triple {
+            let next = lines[i + 1..].iter().find(|l| !l.trim().is_empty());
             if !current_block.trim().is_empty() {
-                run_single_block(&current_block, interpreter)?;
-                current_block.clear();
+                if next.is_some_and(|l| continues_block(l)) {
+                    current_block.push('\n');
+                } else {
+                    blocks.push((start, std::mem::take(&mut current_block)));
+                }
             }
             continue;
         }
+        if current_block.is_empty() {
+            start = i + 1;
+        }
+        if line.matches("\"\"\"").count() % 2 == 1 {
+            in_triple = !in_triple;
+        }
         current_block.push_str(line);
         current_block.push('\n');
     }
 
     if !current_block.trim().is_empty() {
-        run_single_block(&current_block, interpreter)?;
+        blocks.push((start, current_block));
     }
-
-    Ok(())
+    blocks
 }
 
-fn run_single_block(block: &str, interpreter: &mut Interpreter) -> Result<(), String> {
-    let tokens = tokenize(block)?; // Lexer already handles debug output.
-    let ast = parse(tokens);
-    interpreter.run(ast);
-    Ok(())
+fn continues_block(line: &str) -> bool {
+    if line.starts_with([' ', '\t']) {
+        return true;
+    }
+    let first = line
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or("");
+    first.eq_ignore_ascii_case("elif") || first.eq_ignore_ascii_case("else")
 }
 
 /// Runs the entire input as a single block (currently unused).
 #[allow(dead_code)]
-pub fn analyze(input: &str, interpreter: &mut Interpreter) -> Result<String, String> {
+pub fn analyze(input: &str, interpreter: &mut Interpreter) -> Result<String, AnalyzeError> {
+    analyze_with_sentinel(input, interpreter, NO_OUTPUT_SENTINEL)
+}
+
+/// [`analyze`], but a script that prints nothing returns `sentinel` instead of
+/// [`NO_OUTPUT_SENTINEL`]; `""` returns the empty output as is. Runs through the same
+/// `run_source` as the CLI, so `--annotate` and `--continue-on-error` settings apply here too.
+pub fn analyze_with_sentinel(
+    input: &str,
+    interpreter: &mut Interpreter,
+    sentinel: &str,
+) -> Result<String, AnalyzeError> {
     interpreter.clear_output();
-    run_single_block(input, interpreter)?;
+    interpreter.run_source(input)?;
+    interpreter.take_skipped().map_err(AnalyzeError::Runtime)?;
     let out = interpreter.take_output();
     if out.trim().is_empty() {
-        Ok("Execution succeeded.".into())
+        Ok(interpreter.plain_text(sentinel))
     } else {
         Ok(out)
     }
 }
+
+/// Turns a natural-language prompt into NeuroChain DSL via the MacroIntent pipeline, without
+/// running it. Uses the macro model when it loads (`NC_MACRO_MODEL` / `NC_MODELS_DIR`), otherwise
+/// the same keyword heuristics `macro from AI:` falls back to, so it also works offline.
+pub fn generate(prompt: &str) -> AnyResult<String> {
+    let mut interpreter = Interpreter::new();
+    interpreter.macro_to_dsl(prompt).map_err(AnyError::msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_returns_dsl_without_running_it() {
+        let dsl = generate("Show Ping 3 times").unwrap();
+        assert_eq!(dsl.matches("neuro \"Ping\"").count(), 3, "dsl: {dsl}");
+
+        let dsl = generate("Set x to 5 and print it").unwrap();
+        assert!(dsl.contains("set x = 5"), "dsl: {dsl}");
+    }
+
+    #[test]
+    fn blank_lines_do_not_split_an_open_if() {
+        let src = "set a = 1\nif a == 2:\n    neuro \"two\"\n\nelif a == 1:\n    neuro \"one\"\n\nelse:\n    neuro \"other\"\n\nif a == 1:\n    set b = 1\n\n    neuro \"still inside\"\nneuro \"next\"\n";
+        let mut interpreter = Interpreter::new();
+        analyze_blocks(src, &mut interpreter).unwrap();
+        assert_eq!(interpreter.take_output(), "one\nstill inside\nnext");
+    }
+
+    #[test]
+    fn blocks_know_their_first_script_line() {
+        let src = "neuro \"a\"\n\n\nif x:\n    neuro \"b\"\n\n    neuro \"c\"\nneuro \"d\"\n";
+        let starts: Vec<usize> = split_blocks(src).iter().map(|(line, _)| *line).collect();
+        assert_eq!(starts, vec![1, 4]);
+        // The blank line inside the `if` is kept, so later lines keep their numbers.
+        assert_eq!(split_blocks(src)[1].1.lines().count(), 5);
+    }
+
+    #[test]
+    fn trailing_tokens_fail_the_block_before_it_runs() {
+        let mut interpreter = Interpreter::new();
+        let err = analyze("neuro \"first\"\nset x = 2 3\n", &mut interpreter).unwrap_err();
+        assert!(matches!(&err, AnalyzeError::Parse(msg) if msg.contains("Unexpected token '3'")));
+        assert_eq!(interpreter.take_output(), "");
+        assert!(check_blocks("set x = 2\n\nneuro x x\n").is_err());
+    }
+
+    #[test]
+    fn triple_quoted_strings_keep_blank_lines() {
+        let src = "set t = \"\"\"first\n\nsecond\"\"\"\n\nneuro t\n";
+        let mut interpreter = Interpreter::new();
+        analyze_blocks(src, &mut interpreter).unwrap();
+        assert_eq!(interpreter.take_output(), "first\n\nsecond");
+    }
+
+    #[test]
+    fn analyze_errors_name_the_failing_stage() {
+        let mut interpreter = Interpreter::new();
+        let lex = analyze("neuro \"open\n", &mut interpreter).unwrap_err();
+        assert!(matches!(lex, AnalyzeError::Lex(_)) && lex.is_syntax());
+        assert!(lex.to_string().starts_with("❌ Missing quote"), "{lex}");
+
+        let runtime =
+            analyze("for i in range(0, \"x\"):\n    neuro i\n", &mut interpreter).unwrap_err();
+        assert!(matches!(runtime, AnalyzeError::Runtime(_)) && !runtime.is_syntax());
+        assert!(
+            runtime.to_string().contains("must be integers"),
+            "{runtime}"
+        );
+    }
+
+    #[test]
+    fn analyze_honors_continue_on_error() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_continue_on_error(true);
+        let src = "neuro \"a\"\nset x = 2 3\nneuro \"b\"\n";
+        let err = analyze(src, &mut interpreter).unwrap_err();
+        assert_eq!(
+            err,
+            AnalyzeError::Runtime("❌ 1 statement failed and was skipped".into())
+        );
+        assert_eq!(interpreter.take_output(), "a\nb");
+    }
+
+    #[test]
+    fn no_output_returns_the_configured_sentinel() {
+        let mut interpreter = Interpreter::new();
+        let src = "set x = 1\n";
+        assert_eq!(analyze(src, &mut interpreter).unwrap(), NO_OUTPUT_SENTINEL);
+        assert_eq!(
+            analyze_with_sentinel(src, &mut interpreter, "(no output)").unwrap(),
+            "(no output)"
+        );
+        assert_eq!(
+            analyze_with_sentinel(src, &mut interpreter, "").unwrap(),
+            ""
+        );
+        // Scripts that print are unaffected.
+        assert_eq!(
+            analyze_with_sentinel("neuro \"hi\"\n", &mut interpreter, "").unwrap(),
+            "hi"
+        );
+    }
+}
@@ -5,11 +5,18 @@ NeuroChain language — help
 Basic syntax:
 ────────────────────────────────
 AI: "path/to/model.onnx"        → Select an ONNX model
+AI: path_var                     → Same, path read from a variable ("{var}" works too)
 macro from AI: ...               → MacroIntent (intent → deterministic DSL template)
 neuro "text"                     → Print a string
+neuro_raw "text"                 → Print without ending the line
 set x = "value"                  → Set a variable
 set x from AI: "input"           → Run the active model into a variable
+  ... else from AI: "b.onnx"     → Backup model(s) if the active one fails
+  ... else "Neutral"             → Default if no model answers
+set x from AI "sst2": "input"    → One-off model (ID or .onnx path) for this line
 neuro x                          → Print a variable
+neuro "score: " + x              → Print any expression
+neuro "Hi {x}"                   → Interpolate variables ({{ }} = literal braces)
 
 Macros (intent → DSL):
 ────────────────────────────────
@@ -31,14 +38,52 @@ elif x != "value":
 else:
     neuro "..."                 → Fallback branch
 
+while i < 3:
+    set i = i + 1               → Repeats while true (capped at 100000 iterations)
+
+for i in range(0, 3):
+    neuro i                     → Runs with i = 0, 1, 2 (end is exclusive)
+
+repeat 3:
+    neuro __iter                → Runs 3 times, __iter = 0, 1, 2
+
 Logical operators:
 ────────────────────────────────
 and, or                        → Example: if a == "X" and b != "Y":
+not                            → Example: if not mood == "Positive":
 
 Arithmetic:
 ────────────────────────────────
 +  -  *  /  %                 → Example: set x = "4" + "2"
+div                            → Floor division: -7 div 2 → -4
+**                             → Power: 2 ** 10 → 1024
+&  |  ^  <<  >>                → Bitwise on integers: 6 & 3 → 2, 1 << 4 → 16
                                → To concat text + number: "" + number
+                               → Add first in a text chain: "n" + num(5 + 2) → n7
+
+Built-in functions:
+────────────────────────────────
+abs_diff(a, b)                 → |a - b|, e.g. set d = abs_diff(3, 10)
+avg(a, b, ...)                 → Mean, e.g. set m = avg(2, 4, 6)
+abs, round, sqrt, min, max     → e.g. set r = round(sqrt(x))
+round(x, 2), format(x, "0.00") → Fixed decimals, e.g. round(3.14159, 2) → 3.14
+word_count(s), char_count(s)   → Word / character counts
+to_upper_first(s)              → "hello" → "Hello"
+capitalize_each_sentence(s)    → "hi. ok!" → "Hi. Ok!"
+upper(s), lower(s), trim(s)    → Case / whitespace
+len(s), substr(s, start, len)  → Length / slice (characters)
+replace(s, from, to)           → Replace every match
+sanitize(s)                    → Strip quotes / edge punctuation: "'Hi!'" → Hi
+contains(s, p) etc.            → Also startswith / endswith; work in if, ignore case
+trim_prefix, trim_suffix       → (s, p): drop one leading / trailing p
+pad_number(n, width)           → pad_number(7, 3) → 007
+param(key)                     → Value of `neurochain -D key=value`
+env("NC_SCRIPT_X")             → Env var; only NC_SCRIPT_* names are readable
+sha256(s)                      → Hex SHA-256 digest
+json_get(s, "a.b.0")           → JSON field as text (empty if missing)
+pick(flag, a, b)               → a if flag is truthy, else b
+random(lo, hi)                 → Random integer (NC_SEED=42 for repeatable runs)
+now() / now_ms()               → Unix time in s / ms (NC_FIXED_TIME pins it)
 
 Comparison operators:
 ────────────────────────────────
@@ -51,10 +96,23 @@ set a = "5"
 set b = "3"
 set sum = a + b
 
+Lists:
+────────────────────────────────
+set xs = ["a", "b", "c"]       → neuro xs prints [a, b, c]
+xs[0], len(xs)                 → First item (index from 0), item count
+split(s, ","), join(xs, ",")   → Text to list and back
+concat_list(xs, ", ")          → List items joined with the separator
+
+Match:
+────────────────────────────────
+set m = match cmd: case "A": "alpha" default: "other"
+                               → First equal case (like ==), else default
+
 Comments:
 ────────────────────────────────
 # Comment                      → Ignored
 // Comment                     → Also supported
+# @model sst2                  → Select a model by ID (like AI:)
 
 Variables:
 ────────────────────────────────
@@ -93,8 +93,10 @@ pub fn resolve_model_path() -> String {
         }
     }
 
-    let base = env::var("NC_MODELS_DIR").unwrap_or_else(|_| "models".to_string());
-    format!("{base}/intent_stellar/model.onnx")
+    format!(
+        "{}/intent_stellar/model.onnx",
+        crate::ai::model::models_root()
+    )
 }
 
 pub fn threshold_from_env() -> Result<Option<f32>> {
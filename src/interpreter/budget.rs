@@ -0,0 +1,83 @@
+//! Resource budget for untrusted scripts (public server deployments).
+//!
+//! One [`Budget`] caps the three things a hostile script can grow: `neuro` output, loop
+//! iterations summed over the whole run, and distinct variables. Limits are off unless set,
+//! either in code ([`Interpreter::with_budget`](super::Interpreter::with_budget)) or through
+//! `NC_MAX_OUTPUT_BYTES`, `NC_MAX_ITERATIONS` and `NC_MAX_VARIABLES`. The per-loop
+//! `MAX_LOOP_ITERATIONS` cap still applies on top.
+
+use std::env;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// Bytes of `neuro` / `neuro_raw` text, before the output prefix.
+    pub max_output_bytes: Option<usize>,
+    /// Loop iterations (`while`, `for`, `repeat`) summed over every loop.
+    pub max_iterations: Option<usize>,
+    /// Distinct variable names; reassigning an existing one is always allowed.
+    pub max_variables: Option<usize>,
+}
+
+impl Budget {
+    /// No limits.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Each limit from its env var if it parses as a non-negative integer, else unlimited.
+    pub fn from_env() -> Self {
+        let limit = |key: &str| env::var(key).ok().and_then(|v| v.trim().parse().ok());
+        Self {
+            max_output_bytes: limit("NC_MAX_OUTPUT_BYTES"),
+            max_iterations: limit("NC_MAX_ITERATIONS"),
+            max_variables: limit("NC_MAX_VARIABLES"),
+        }
+    }
+}
+
+/// A budget plus what has been spent against it so far.
+#[derive(Debug, Default)]
+pub(super) struct Meter {
+    pub(super) budget: Budget,
+    output_bytes: usize,
+    iterations: usize,
+}
+
+impl Meter {
+    pub(super) fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            ..Self::default()
+        }
+    }
+
+    pub(super) fn output(&mut self, bytes: usize) -> Result<(), String> {
+        self.output_bytes = self.output_bytes.saturating_add(bytes);
+        match self.budget.max_output_bytes {
+            Some(max) if self.output_bytes > max => Err(format!(
+                "❌ Budget exceeded: output is over {max} bytes (NC_MAX_OUTPUT_BYTES)"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn iteration(&mut self) -> Result<(), String> {
+        self.iterations += 1;
+        match self.budget.max_iterations {
+            Some(max) if self.iterations > max => Err(format!(
+                "❌ Budget exceeded: more than {max} loop iterations (NC_MAX_ITERATIONS)"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Called before a new variable name is added to `existing` ones.
+    pub(super) fn new_variable(&self, existing: usize) -> Result<(), String> {
+        match self.budget.max_variables {
+            Some(max) if existing >= max => Err(format!(
+                "❌ Budget exceeded: more than {max} variables (NC_MAX_VARIABLES)"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
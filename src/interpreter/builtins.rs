@@ -0,0 +1,364 @@
+//! Built-in functions callable from expressions (`set x = abs_diff(a, b)`, `set u = upper(name)`).
+//!
+//! Arguments arrive already evaluated. Like the rest of `eval_expr`, failures are
+//! reported as a `❌ ...` string value instead of aborting the script.
+
+use std::cmp::Ordering;
+use std::env;
+
+use sha2::{Digest, Sha256};
+
+use super::clock::Clock;
+use super::rng::Rng;
+use super::value::Value;
+
+/// Dispatches a built-in call by name. Random built-ins draw from the interpreter's `rng`,
+/// time built-ins read its `clock`.
+pub(super) fn call(name: &str, args: &[String], rng: &Rng, clock: &dyn Clock) -> String {
+    match name {
+        "random" => random(args, rng),
+        "now" => no_args(name, args, || clock.now().as_secs().to_string()),
+        "now_ms" => no_args(name, args, || clock.now().as_millis().to_string()),
+        "abs_diff" => abs_diff(args),
+        "avg" => avg(args),
+        // Identity on numbers; marks arithmetic inside a text chain (`"n" + num(5 + 2)`).
+        "num" => unary_math(name, args, |n| n),
+        "abs" => unary_math(name, args, f64::abs),
+        "round" if args.len() == 2 => fixed_decimals(name, &args[0], &args[1]),
+        "round" => unary_math(name, args, f64::round),
+        "sqrt" => unary_math(name, args, f64::sqrt), // Negative input gives NaN, like `/ 0`.
+        "min" => extreme(name, args, Ordering::Less),
+        "max" => extreme(name, args, Ordering::Greater),
+        "word_count" => one_arg(name, args, |s| s.split_whitespace().count().to_string()),
+        "char_count" => one_arg(name, args, |s| s.chars().count().to_string()),
+        "to_upper_first" => one_arg(name, args, to_upper_first),
+        "capitalize_each_sentence" => one_arg(name, args, capitalize_each_sentence),
+        "upper" => one_arg(name, args, str::to_uppercase),
+        "lower" => one_arg(name, args, str::to_lowercase),
+        "trim" => one_arg(name, args, |s| s.trim().to_string()),
+        // Same cleanup the macro layer applies to extracted text.
+        "sanitize" => one_arg(name, args, super::sanitize_text),
+        "len" => one_arg(name, args, |s| s.chars().count().to_string()),
+        "sha256" => one_arg(name, args, |s| hex::encode(Sha256::digest(s.as_bytes()))),
+        "substr" => substr(args),
+        "pad_number" => pad_number(args),
+        "pick" => match args {
+            [flag, a, b] => {
+                if truthy(flag) {
+                    a.clone()
+                } else {
+                    b.clone()
+                }
+            }
+            _ => format!("❌ pick() takes 3 arguments, got {}", args.len()),
+        },
+        "trim_prefix" => match args {
+            [s, p] => s.strip_prefix(p.as_str()).unwrap_or(s).to_string(),
+            _ => format!("❌ trim_prefix() takes 2 arguments, got {}", args.len()),
+        },
+        "trim_suffix" => match args {
+            [s, p] => s.strip_suffix(p.as_str()).unwrap_or(s).to_string(),
+            _ => format!("❌ trim_suffix() takes 2 arguments, got {}", args.len()),
+        },
+        "format" => match args {
+            // `"0.00"` asks for two decimals; the digits count after the dot, the rest is ignored.
+            [x, pattern] => {
+                let decimals = pattern.split_once('.').map_or(0, |(_, d)| d.len());
+                fixed_decimals(name, x, &decimals.to_string())
+            }
+            _ => format!("❌ format() takes 2 arguments, got {}", args.len()),
+        },
+        "env" => one_arg(name, args, script_env),
+        "contains" | "startswith" | "endswith" => match predicate(name, args) {
+            Some(hit) => hit.to_string(),
+            None => format!("❌ {name}() takes 2 arguments, got {}", args.len()),
+        },
+        "json_get" => match args {
+            [doc, path] => json_get(doc, path),
+            _ => format!("❌ json_get() takes 2 arguments, got {}", args.len()),
+        },
+        "replace" => match args {
+            [s, from, to] if !from.is_empty() => s.replace(from.as_str(), to),
+            [_, _, _] => "❌ replace() needs a non-empty search string".into(),
+            _ => format!("❌ replace() takes 3 arguments, got {}", args.len()),
+        },
+        _ => format!("❌ Unknown function: {name}"),
+    }
+}
+
+/// `split(s, sep)`: the pieces of `s` between each `sep`, as a list. Every piece stays text
+/// (`split("1,2", ",")` holds `"1"` and `"2"`), so `join` gives back exactly `s`.
+pub(super) fn split(args: &[String]) -> Value {
+    match args {
+        [s, sep] if !sep.is_empty() => Value::List(
+            s.split(sep.as_str())
+                .map(|piece| Value::Str(piece.to_string()))
+                .collect(),
+        ),
+        [_, _] => Value::Str("❌ split() needs a non-empty separator".into()),
+        _ => Value::Str(format!("❌ split() takes 2 arguments, got {}", args.len())),
+    }
+}
+
+/// `join(xs, sep)` and `concat_list(xs, sep)`: the items of list `xs` in their printed form
+/// (numbers as `2`, not `"2"`; nested lists bracketed), separated by `sep`. The inverse of
+/// `split`: `join(split(s, sep), sep)` is `s`.
+pub(super) fn join(name: &str, list: &Value, sep: &str) -> String {
+    match list.as_list() {
+        Some(items) => items
+            .iter()
+            .map(Value::to_string)
+            .collect::<Vec<_>>()
+            .join(sep),
+        None => format!("❌ {name}() expects a list, got '{list}'"),
+    }
+}
+
+/// `contains` / `startswith` / `endswith` (case-insensitive) for conditions and expressions.
+/// `None` for any other name or a wrong argument count.
+pub(super) fn predicate(name: &str, args: &[String]) -> Option<bool> {
+    let [text, part] = args else {
+        return None;
+    };
+    let (text, part) = (text.to_lowercase(), part.to_lowercase());
+    match name {
+        "contains" => Some(text.contains(&part)),
+        "startswith" => Some(text.starts_with(&part)),
+        "endswith" => Some(text.ends_with(&part)),
+        _ => None,
+    }
+}
+
+/// Truthiness for `pick` and bare conditions: everything except empty, `false`, `0` and `None`
+/// (case-insensitive).
+pub(super) fn truthy(flag: &str) -> bool {
+    let f = flag.trim();
+    !(f.is_empty() || f.eq_ignore_ascii_case("false") || f == "0" || f.eq_ignore_ascii_case("none"))
+}
+
+fn no_args(name: &str, args: &[String], f: impl Fn() -> String) -> String {
+    match args {
+        [] => f(),
+        _ => format!("❌ {name}() takes no arguments, got {}", args.len()),
+    }
+}
+
+fn one_arg(name: &str, args: &[String], f: impl Fn(&str) -> String) -> String {
+    match args {
+        [s] => f(s),
+        _ => format!("❌ {name}() takes 1 argument, got {}", args.len()),
+    }
+}
+
+fn numbers(name: &str, args: &[String]) -> Result<Vec<f64>, String> {
+    args.iter()
+        .map(|a| {
+            a.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("❌ {name}() expects numbers, got '{}'", a.trim()))
+        })
+        .collect()
+}
+
+fn unary_math(name: &str, args: &[String], f: fn(f64) -> f64) -> String {
+    if args.len() != 1 {
+        return format!("❌ {name}() takes 1 argument, got {}", args.len());
+    }
+    match numbers(name, args) {
+        Ok(n) => format!("{}", f(n[0]) + 0.0), // `+ 0.0` turns `-0` into `0`.
+        Err(e) => e,
+    }
+}
+
+/// `min`/`max`: numeric when every argument is a number, otherwise a case-insensitive
+/// text comparison (the same rule `if a < b:` uses).
+fn extreme(name: &str, args: &[String], want: Ordering) -> String {
+    if args.len() < 2 {
+        return format!("❌ {name}() needs at least 2 arguments, got {}", args.len());
+    }
+    if let Ok(n) = numbers(name, args) {
+        let best = n.into_iter().reduce(|a, b| {
+            if b.partial_cmp(&a) == Some(want) {
+                b
+            } else {
+                a
+            }
+        });
+        return format!("{}", best.unwrap_or(f64::NAN));
+    }
+    args.iter()
+        .map(|a| a.trim())
+        .reduce(|a, b| {
+            if b.to_ascii_lowercase().cmp(&a.to_ascii_lowercase()) == want {
+                b
+            } else {
+                a
+            }
+        })
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// `random(lo, hi)`: uniform integer in `[lo, hi]`; bounds may be given in either order.
+fn random(args: &[String], rng: &Rng) -> String {
+    let [lo, hi] = args else {
+        return format!("❌ random() takes 2 arguments, got {}", args.len());
+    };
+    match (lo.trim().parse::<i64>(), hi.trim().parse::<i64>()) {
+        (Ok(a), Ok(b)) => rng.range_inclusive(a.min(b), a.max(b)).to_string(),
+        _ => format!(
+            "❌ random() expects integers, got '{}', '{}'",
+            lo.trim(),
+            hi.trim()
+        ),
+    }
+}
+
+/// Largest number of decimals `round(x, digits)` and `format(x, "0.00")` will print.
+const MAX_DECIMALS: i64 = 10;
+
+/// `x` printed with exactly `digits` decimals, clamped to `0..=MAX_DECIMALS`. A negative value
+/// that rounds to zero prints without its sign: `round(-0.004, 2)` is `0.00`.
+fn fixed_decimals(name: &str, x: &str, digits: &str) -> String {
+    let x = match x.trim().parse::<f64>() {
+        Ok(x) => x,
+        Err(_) => return format!("❌ {name}() expects a number, got '{}'", x.trim()),
+    };
+    match digits.trim().parse::<i64>() {
+        Ok(d) => {
+            let out = format!("{:.*}", d.clamp(0, MAX_DECIMALS) as usize, x);
+            match out.strip_prefix('-') {
+                Some(zero) if zero.bytes().all(|b| b == b'0' || b == b'.') => zero.to_string(),
+                _ => out,
+            }
+        }
+        Err(_) => format!(
+            "❌ {name}() expects an integer digit count, got '{}'",
+            digits.trim()
+        ),
+    }
+}
+
+fn abs_diff(args: &[String]) -> String {
+    if args.len() != 2 {
+        return format!("❌ abs_diff() takes 2 arguments, got {}", args.len());
+    }
+    match numbers("abs_diff", args) {
+        Ok(n) => format!("{}", (n[0] - n[1]).abs()),
+        Err(e) => e,
+    }
+}
+
+fn avg(args: &[String]) -> String {
+    if args.is_empty() {
+        return "❌ avg() needs at least 1 argument".into();
+    }
+    match numbers("avg", args) {
+        Ok(n) => format!("{}", n.iter().sum::<f64>() / n.len() as f64),
+        Err(e) => e,
+    }
+}
+
+/// `substr(s, start[, len])`, counted in characters; out-of-range parts are clipped.
+fn substr(args: &[String]) -> String {
+    let (s, rest) = match args {
+        [s, rest @ ..] if (1..=2).contains(&rest.len()) => (s, rest),
+        _ => return format!("❌ substr() takes 2 or 3 arguments, got {}", args.len()),
+    };
+    let index = |raw: &String| {
+        raw.trim().parse::<usize>().map_err(|_| {
+            format!(
+                "❌ substr() expects non-negative integers, got '{}'",
+                raw.trim()
+            )
+        })
+    };
+    let start = match index(&rest[0]) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    let len = match rest.get(1).map(index) {
+        Some(Ok(n)) => n,
+        Some(Err(e)) => return e,
+        None => usize::MAX,
+    };
+    s.chars().skip(start).take(len).collect()
+}
+
+/// `pad_number(n, width)`: zero-pads an integer; longer numbers are left as-is.
+fn pad_number(args: &[String]) -> String {
+    let [n, width] = args else {
+        return format!("❌ pad_number() takes 2 arguments, got {}", args.len());
+    };
+    match (n.trim().parse::<i64>(), width.trim().parse::<usize>()) {
+        (Ok(n), Ok(width)) => format!("{n:0width$}"),
+        _ => format!(
+            "❌ pad_number() expects an integer and a width, got '{}', '{}'",
+            n.trim(),
+            width.trim()
+        ),
+    }
+}
+
+/// `json_get(doc, "a.b.0")`: the value at a dotted path. Numeric segments also index arrays.
+/// Strings come back unquoted, objects and arrays as compact JSON; a missing path, `null`
+/// or invalid JSON gives an empty string.
+fn json_get(doc: &str, path: &str) -> String {
+    let Ok(root) = serde_json::from_str::<serde_json::Value>(doc) else {
+        return String::new();
+    };
+    let found = path
+        .split('.')
+        .filter(|seg| !seg.is_empty())
+        .try_fold(&root, |v, seg| match v {
+            serde_json::Value::Array(items) => seg.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => v.get(seg),
+        });
+    match found {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Only env vars with this prefix are readable from scripts, so a script on the server can't
+/// read `NC_API_KEY` or anything else the process was started with.
+const SCRIPT_ENV_PREFIX: &str = "NC_SCRIPT_";
+
+/// `env("NC_SCRIPT_NAME")`: the variable's value, empty when unset.
+fn script_env(key: &str) -> String {
+    let key = key.trim();
+    if !key.starts_with(SCRIPT_ENV_PREFIX) {
+        return format!("❌ env() can only read {SCRIPT_ENV_PREFIX}* variables, got '{key}'");
+    }
+    env::var(key).unwrap_or_default()
+}
+
+fn to_upper_first(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Upper-cases the first letter of the text and the first letter after each `.`, `!` or `?`.
+/// A digit cancels it, so "3.5 apples" stays as is.
+fn capitalize_each_sentence(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut at_start = true;
+    for ch in s.chars() {
+        if at_start && ch.is_alphabetic() {
+            out.extend(ch.to_uppercase());
+            at_start = false;
+            continue;
+        }
+        if matches!(ch, '.' | '!' | '?') {
+            at_start = true;
+        } else if ch.is_alphanumeric() {
+            at_start = false;
+        }
+        out.push(ch);
+    }
+    out
+}
@@ -0,0 +1,67 @@
+//! Wall-clock time for the time built-ins (`now()`, `now_ms()`).
+//!
+//! The `Interpreter` reads time only through its `Clock`, so tests can pin it with
+//! `FixedClock` and CLI runs can pin it with `NC_FIXED_TIME=<unix seconds>`. Without either,
+//! it is the system clock.
+
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub trait Clock: Send {
+    /// Time since the Unix epoch.
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        // A system clock set before 1970 reads as the epoch.
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+/// Always reports the same instant.
+pub struct FixedClock(pub Duration);
+
+impl FixedClock {
+    pub fn from_unix_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Duration {
+        self.0
+    }
+}
+
+/// `FixedClock` at `NC_FIXED_TIME` if it parses as whole Unix seconds, otherwise the system clock.
+pub fn from_env() -> Box<dyn Clock> {
+    match env::var("NC_FIXED_TIME")
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+    {
+        Some(secs) => Box::new(FixedClock::from_unix_secs(secs)),
+        None => Box::new(SystemClock),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_clock_does_not_move() {
+        let clock = FixedClock::from_unix_secs(1_700_000_000);
+        assert_eq!(clock.now(), Duration::from_secs(1_700_000_000));
+        assert_eq!(clock.now(), clock.now());
+    }
+
+    #[test]
+    fn system_clock_is_after_2020() {
+        assert!(SystemClock.now() > Duration::from_secs(1_577_836_800));
+    }
+}
@@ -1,21 +1,40 @@
 //! NeuroChain interpreter.
 //!
 //! Executes the parsed AST and provides:
-//! - Variables (`set`), arithmetic and comparisons
-//! - `if`/`elif`/`else` + `and`/`or` boolean logic
-//! - AI classification via `AI:` + `set ... from AI:`
+//! - Variables (`set`, kept in assignment order via `Variables`), arithmetic and comparisons
+//! - Built-in functions (`abs_diff`, `avg`) via `builtins`
+//! - `if`/`elif`/`else` + `and`/`or`/`not` boolean logic
+//! - AI classification via `AI:` + `set ... from AI:` (with `else from AI` backup models)
 //! - MacroIntent: `macro from AI:` -> intent classifier -> deterministic DSL template -> run
 
-use crate::ai::model::{AIModel, ModelKind};
-use crate::lexer::tokenize;
-use crate::parser::{parse as parse_nodes, ASTNode, BinaryOperator, BoolExpr, Expr};
+use crate::ai::model::{models_root, resolve_model_path, AIModel, ModelKind};
+use crate::engine::{panic_message, AnalyzeError};
+use crate::lexer::{tokenize, tokenize_with_lines};
+use crate::parser::{first_error, parse as parse_nodes, ASTNode, BinaryOperator, BoolExpr, Expr};
 use regex::Regex;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::env;
 use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::io::{self, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::sync::OnceLock;
+use std::time::Instant;
+
+mod budget;
+mod builtins;
+mod clock;
+mod rng;
+mod value;
+mod vars;
+
+pub use budget::Budget;
+pub use clock::{Clock, FixedClock, SystemClock};
+pub use value::Value;
+pub use vars::Variables;
+
+/// Safety cap for `while`/`for` loops so a runaway loop can't hang the CLI or server.
+pub const MAX_LOOP_ITERATIONS: usize = 100_000;
 
 static EMBEDDED_SET_RE: OnceLock<Regex> = OnceLock::new();
 
@@ -169,6 +188,31 @@ fn clean_generated_dsl(raw: &str, _instr: &str) -> String {
 }
 
 /* --- Logging --------------------------------------------------------- */
+/// Prefix for `neuro` lines on stdout (`NC_OUTPUT_PREFIX`, may be empty). The log file and
+/// `take_output` are not affected.
+fn output_prefix() -> String {
+    env::var("NC_OUTPUT_PREFIX").unwrap_or_else(|_| "neuro: ".to_string())
+}
+
+fn ascii_only_enabled() -> bool {
+    env::var("NC_ASCII_ONLY")
+        .map(|v| {
+            let v = v.trim().to_ascii_lowercase();
+            matches!(v.as_str(), "1" | "true" | "yes" | "on")
+        })
+        .unwrap_or(false)
+}
+
+/// Drops every non-ASCII character, plus the one space after a leading symbol:
+/// `"✅ Done"` → `"Done"`. Other spaces stay, so indents and `neuro_raw` pieces line up.
+fn ascii_only(s: &str) -> String {
+    let kept: String = s.chars().filter(char::is_ascii).collect();
+    match kept.strip_prefix(' ') {
+        Some(rest) if !s.starts_with(|c: char| c.is_ascii()) => rest.to_string(),
+        _ => kept,
+    }
+}
+
 fn logging_enabled() -> bool {
     std::env::var("NEUROCHAIN_OUTPUT_LOG")
         .map(|v| {
@@ -224,8 +268,25 @@ fn macro_model_path() -> String {
     if let Ok(p) = env::var("NC_MACRO_MODEL_PATH") {
         return p;
     }
-    let base = env::var("NC_MODELS_DIR").unwrap_or_else(|_| "models".to_string());
-    format!("{base}/intent_macro/model.onnx")
+    format!("{}/intent_macro/model.onnx", models_root())
+}
+
+/// Model for `set x from AI "spec": ...`: an `.onnx` path as-is, else a model ID (`sst2`,
+/// `toxic`, ...) under [`models_root`]. Unknown IDs are returned unchanged and fail to load.
+fn inline_model_path(spec: &str) -> String {
+    if spec.ends_with(".onnx") {
+        return spec.to_string();
+    }
+    resolve_model_path(&models_root(), &spec.to_ascii_lowercase())
+        .unwrap_or_else(|| spec.to_string())
+}
+
+/// Whether a model of kind `loaded` becomes the `set x from AI:` model when `active` is. The
+/// macro model also has its own slot, so `macro from AI:` and `set x from AI:` can both be
+/// active: loading it keeps an active classifier, and loading a classifier keeps the macro
+/// model. It only takes the `set x from AI:` slot when no classifier holds it.
+fn becomes_active_model(loaded: ModelKind, active: Option<ModelKind>) -> bool {
+    loaded != ModelKind::MacroIntent || active.is_none_or(|kind| kind == ModelKind::MacroIntent)
 }
 
 fn macro_intent_threshold() -> f32 {
@@ -235,6 +296,15 @@ fn macro_intent_threshold() -> f32 {
         .unwrap_or(0.35)
 }
 
+/// Longest `macro from AI:` prompt (in characters) the template pipeline will look at.
+/// Every template runs several regex passes over the prompt, so huge inputs are refused up front.
+fn macro_prompt_limit() -> usize {
+    env::var("NC_MAX_MACRO_PROMPT")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(2000)
+}
+
 /* --- Tail normalization (helper) ------------------------------------- */
 #[allow(dead_code)]
 fn normalise_tail(mut tail: String) -> Option<String> {
@@ -343,284 +413,669 @@ fn auto_fix_dsl(src: &str, prompt: &str) -> String {
     fixed.join("\n")
 }
 
+/// `&`, `|`, `^`, `<<`, `>>` on `i64`. Non-integer operands and shifts outside `0..=63` give a
+/// `❌ ...` value, like the other arithmetic errors.
+fn bitwise(op: &BinaryOperator, l: &str, r: &str) -> String {
+    let (Ok(a), Ok(b)) = (l.parse::<i64>(), r.parse::<i64>()) else {
+        return format!("❌ Bitwise operators need integers, got '{l}' and '{r}'");
+    };
+    let shift = |f: fn(i64, u32) -> Option<i64>| match u32::try_from(b).ok().and_then(|b| f(a, b)) {
+        Some(v) => v.to_string(),
+        None => format!("❌ Shift amount must be between 0 and 63, got {b}"),
+    };
+    match op {
+        BinaryOperator::BitAnd => (a & b).to_string(),
+        BinaryOperator::BitOr => (a | b).to_string(),
+        BinaryOperator::BitXor => (a ^ b).to_string(),
+        BinaryOperator::Shl => shift(i64::checked_shl),
+        BinaryOperator::Shr => shift(i64::checked_shr),
+        _ => unreachable!("not a bitwise operator: {op:?}"),
+    }
+}
+
+/// `--continue-on-error` report for a skipped statement: `❌ line 2: Unknown ...` on stderr.
+fn report_statement_error(line: usize, err: &str) {
+    let err = err.strip_prefix("❌").unwrap_or(err).trim_start();
+    eprintln!("❌ line {line}: {err}");
+    append_log(&format!("line {line}: {err}"));
+}
+
 /* --- Interpreter ----------------------------------------------------- */
 pub struct Interpreter {
     ai_model: Option<AIModel>,
+    ai_model_path: Option<String>,
     macro_model: Option<AIModel>,
-    pub variables: HashMap<String, String>,
+    /// Every model loaded with `AI:`, by kind; `set x from AI "sst2": ...` picks from here.
+    models: HashMap<ModelKind, AIModel>,
+    pub variables: Variables,
+    /// Run parameters (`neurochain -D key=value`), read with `param("key")`.
+    params: HashMap<String, String>,
     output: Vec<String>,
+    /// Line started by `neuro_raw` and not yet ended; the prefix is already on stdout.
+    partial_line: Option<String>,
+    rng: rng::Rng,
+    /// Time source for `now()` / `now_ms()`.
+    clock: Box<dyn Clock>,
+    /// `NC_ASCII_ONLY=1`: strip non-ASCII (emojis, accents) from `neuro` output.
+    ascii_only: bool,
+    /// Output / iteration / variable limits and what the script has used of them.
+    budget: budget::Meter,
+    /// Wall-clock point after which the next loop iteration aborts the run (server time limit).
+    deadline: Option<Instant>,
+    /// `--annotate`: printed `neuro` lines start with `[L<n>]`, the source line of the statement.
+    annotate: bool,
+    /// Line of the statement being run, when the source was lexed with line markers.
+    current_line: Option<usize>,
+    /// `--continue-on-error`: a failing top-level statement is reported and skipped.
+    continue_on_error: bool,
+    /// Statements skipped that way since the last [`Interpreter::take_skipped`].
+    skipped: usize,
+    /// Print `neuro` lines to stdout as they run; off for `--json`, which prints them at the end.
+    echo: bool,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
         Self {
             ai_model: None,
+            ai_model_path: None,
             macro_model: None,
-            variables: HashMap::new(),
+            models: HashMap::new(),
+            variables: Variables::new(),
+            params: HashMap::new(),
             output: Vec::new(),
+            partial_line: None,
+            rng: rng::Rng::from_env(),
+            clock: clock::from_env(),
+            ascii_only: ascii_only_enabled(),
+            budget: budget::Meter::new(Budget::from_env()),
+            deadline: None,
+            annotate: false,
+            current_line: None,
+            continue_on_error: false,
+            skipped: 0,
+            echo: true,
+        }
+    }
+
+    /// An interpreter that enforces `budget` instead of the `NC_MAX_*` env limits.
+    pub fn with_budget(budget: Budget) -> Self {
+        let mut interp = Self::new();
+        interp.budget = budget::Meter::new(budget);
+        interp
+    }
+
+    /// Back to a fresh interpreter: no variables, no pending output, no loaded models.
+    /// Run parameters (`-D`), the clock, the `--annotate` / `--continue-on-error` / echo modes
+    /// and the budget limits are kept (its usage starts over); they come from the caller, not the
+    /// script.
+    pub fn reset(&mut self) {
+        let params = std::mem::take(&mut self.params);
+        let clock = std::mem::replace(&mut self.clock, Box::new(SystemClock));
+        let (annotate, continue_on_error, echo) =
+            (self.annotate, self.continue_on_error, self.echo);
+        let budget = self.budget.budget;
+        *self = Self::with_budget(budget);
+        self.params = params;
+        self.clock = clock;
+        self.annotate = annotate;
+        self.continue_on_error = continue_on_error;
+        self.echo = echo;
+    }
+
+    /// Prefix printed `neuro` lines with `[L<n>]`, the script line they came from. Only stdout
+    /// changes; `take_output` and the logs stay as they are.
+    pub fn set_annotate(&mut self, on: bool) {
+        self.annotate = on;
+    }
+
+    /// Stop the run at the first loop iteration after `at`, with a `❌ Time limit ...` error.
+    /// Lets a caller that has given up on the run (a server timeout) get its thread back.
+    pub fn set_deadline(&mut self, at: Instant) {
+        self.deadline = Some(at);
+    }
+
+    /// Replace the time source, e.g. a `FixedClock` so `now()` is reproducible in tests.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Keep going after a failing top-level statement of `run_source`: its lex, parse or
+    /// runtime error (or panic) goes to stderr with its script line, and the next statement
+    /// runs. Off by default, where the first error stops the script.
+    pub fn set_continue_on_error(&mut self, on: bool) {
+        self.continue_on_error = on;
+    }
+
+    /// `Err` naming how many statements `--continue-on-error` skipped since the last call, so
+    /// the run as a whole still fails; `Ok` when none were.
+    pub(crate) fn take_skipped(&mut self) -> Result<(), String> {
+        match std::mem::take(&mut self.skipped) {
+            0 => Ok(()),
+            1 => Err("❌ 1 statement failed and was skipped".into()),
+            n => Err(format!("❌ {n} statements failed and were skipped")),
         }
     }
 
+    /// Whether `neuro` lines go to stdout as they run (the default). Off, they are only kept
+    /// for `take_output`, and status lines such as `✅ Model loaded` go to stderr.
+    pub fn set_echo(&mut self, on: bool) {
+        self.echo = on;
+    }
+
+    pub fn set_param(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.params.insert(key.into(), value.into());
+    }
+
+    /// `param("key")`: the `-D` value, else env `NC_PARAM_<KEY>`, else empty. Only the
+    /// prefixed env var is consulted so scripts (e.g. on the server) can't read arbitrary env.
+    fn param(&self, args: &[String]) -> String {
+        let [key] = args else {
+            return format!("❌ param() takes 1 argument, got {}", args.len());
+        };
+        self.params
+            .get(key)
+            .cloned()
+            .or_else(|| env::var(format!("NC_PARAM_{}", key.to_ascii_uppercase())).ok())
+            .unwrap_or_default()
+    }
+
+    /// Path of the active `set x from AI:` model: the last classifier loaded with `AI:`, or
+    /// the macro model when no classifier is loaded.
+    pub fn model_path(&self) -> Option<&str> {
+        self.ai_model_path.as_deref()
+    }
+
     pub fn clear_output(&mut self) {
         self.output.clear();
+        self.partial_line = None;
     }
 
+    /// Output lines joined with `\n`. A line still open from `neuro_raw` is ended first and
+    /// comes last.
     pub fn take_output(&mut self) -> String {
+        self.end_partial_line();
         let out = self.output.join("\n");
         self.output.clear();
         out
     }
 
-    fn emit_neuro(&mut self, msg: &str) {
-        println!("neuro: {msg}");
-        append_log(&format!("neuro: {msg}"));
-        self.output.push(msg.to_string());
+    /// `msg` as clients should see it: ASCII-only when `NC_ASCII_ONLY` is on, else unchanged.
+    pub fn plain_text(&self, msg: &str) -> String {
+        if self.ascii_only {
+            ascii_only(msg)
+        } else {
+            msg.to_string()
+        }
     }
 
-    pub fn run(&mut self, ast: Vec<ASTNode>) {
-        for node in ast {
-            match node {
-                ASTNode::AIModel(path) => {
-                    self.ai_model =
-                        Some(AIModel::new(&path).expect("failed to load model from path"));
-                    println!("✅ Model loaded: {path}");
-                    if let Some(m) = &self.ai_model {
-                        if matches!(m.kind(), ModelKind::MacroIntent) {
-                            self.macro_model = Some(m.clone());
-                        }
-                    }
+    /// What starts a printed output line: the `--annotate` marker, then `NC_OUTPUT_PREFIX`.
+    fn line_start(&self) -> String {
+        match self.current_line {
+            Some(n) if self.annotate => format!("[L{n}] {}", output_prefix()),
+            _ => output_prefix(),
+        }
+    }
+
+    fn emit_neuro(&mut self, msg: &str) {
+        let msg = self.plain_text(msg);
+        let line = match self.partial_line.take() {
+            Some(start) => {
+                if self.echo {
+                    println!("{msg}");
+                }
+                start + &msg
+            }
+            None => {
+                if self.echo {
+                    println!("{}{msg}", self.line_start());
                 }
+                msg
+            }
+        };
+        append_log(&format!("neuro: {line}"));
+        self.output.push(line);
+    }
 
-                ASTNode::Neuro(arg) => {
-                    let msg = if arg.starts_with('"') && arg.ends_with('"') {
-                        arg.trim_matches('"').to_string()
-                    } else if let Some(v) = self.variables.get(&arg) {
-                        v.trim().to_string()
-                    } else {
-                        arg.trim_matches('"').trim().to_string()
-                    };
-                    self.emit_neuro(&msg);
+    /// `neuro_raw`: prints `msg` without a newline and keeps it as the start of the next
+    /// output line, which the next `neuro` (or [`Interpreter::end_partial_line`]) completes.
+    fn emit_neuro_raw(&mut self, msg: &str) {
+        let msg = self.plain_text(msg);
+        if self.echo {
+            if self.partial_line.is_none() {
+                print!("{}", self.line_start());
+            }
+            print!("{msg}");
+            let _ = io::stdout().flush();
+        }
+        self.partial_line
+            .get_or_insert_with(String::new)
+            .push_str(&msg);
+    }
+
+    /// Ends a line left open by `neuro_raw` as if a `neuro ""` followed. No-op otherwise.
+    pub fn end_partial_line(&mut self) {
+        if self.partial_line.is_some() {
+            self.emit_neuro("");
+        }
+    }
+
+    /// Tokenizes, parses and runs `src` as a single block, returning the `neuro` output of this
+    /// call (earlier pending output is dropped). Variables and models persist across calls.
+    ///
+    /// ```
+    /// use neurochain::Interpreter;
+    ///
+    /// let mut i = Interpreter::new();
+    /// assert_eq!(i.run_str("neuro \"hi\"").unwrap(), "hi");
+    /// i.run_str("set n = 2 + 3").unwrap();
+    /// assert_eq!(i.run_str("neuro \"n = {n}\"").unwrap(), "n = 5");
+    /// assert!(i.run_str("set x = 2 3").is_err());
+    /// ```
+    pub fn run_str(&mut self, src: &str) -> Result<String, String> {
+        self.clear_output();
+        self.run_source(src).map_err(|err| err.to_string())?;
+        Ok(self.take_output())
+    }
+
+    /// Lexer → parser → `run`, keeping pending output. A block with a parse error is rejected
+    /// before any of it runs; the error names the stage that failed.
+    pub(crate) fn run_source(&mut self, src: &str) -> Result<(), AnalyzeError> {
+        self.run_source_at(src, 1)
+    }
+
+    /// [`Interpreter::run_source`] for a block that starts on line `first_line` of the script,
+    /// so `--annotate` reports script lines rather than block lines.
+    pub(crate) fn run_source_at(
+        &mut self,
+        src: &str,
+        first_line: usize,
+    ) -> Result<(), AnalyzeError> {
+        if self.continue_on_error {
+            self.run_source_recovering(src, first_line);
+            return Ok(());
+        }
+        let tokens = if self.annotate {
+            tokenize_with_lines(src, first_line)
+        } else {
+            tokenize(src)
+        };
+        let ast = parse_nodes(tokens.map_err(AnalyzeError::Lex)?);
+        if let Some(err) = first_error(&ast) {
+            return Err(AnalyzeError::Parse(err.to_string()));
+        }
+        self.run(ast).map_err(AnalyzeError::Runtime)
+    }
+
+    /// `run_source_at` with `continue_on_error`: each top-level statement runs on its own, and
+    /// a failing one is reported instead of ending the block. A lex error still skips the
+    /// whole block, since there are no statements to recover.
+    fn run_source_recovering(&mut self, src: &str, first_line: usize) {
+        let ast = match tokenize_with_lines(src, first_line) {
+            Ok(tokens) => parse_nodes(tokens),
+            Err(err) => {
+                self.skipped += 1;
+                report_statement_error(first_line, &err);
+                return;
+            }
+        };
+        // Errors are reported at the top-level statement's line, not the nested one that failed.
+        let mut line = first_line;
+        for node in &ast {
+            if let ASTNode::Line(n) = node {
+                line = *n;
+            }
+            let result = match first_error(std::slice::from_ref(node)) {
+                Some(err) => Err(err.to_string()),
+                None => {
+                    catch_unwind(AssertUnwindSafe(|| self.exec(node))).unwrap_or_else(|panic| {
+                        let msg = panic_message(panic.as_ref()).unwrap_or("internal panic");
+                        Err(format!("❌ Panic: {msg}"))
+                    })
                 }
+            };
+            if let Err(err) = result {
+                self.skipped += 1;
+                report_statement_error(line, &err);
+            }
+        }
+    }
+
+    /// Executes a parsed program. Runtime aborts (e.g. a runaway `while`) surface as `Err`.
+    ///
+    /// Statements run strictly in source order, depth-first: a nested block finishes before the
+    /// next sibling statement starts, so `take_output` lines always follow execution order.
+    pub fn run(&mut self, ast: Vec<ASTNode>) -> Result<(), String> {
+        self.run_block(&ast)
+    }
 
-                ASTNode::SetVar(name, expr) => {
-                    let val = self.eval_expr(&expr).trim().to_string();
-                    self.variables.insert(name.clone(), val);
+    fn run_block(&mut self, nodes: &[ASTNode]) -> Result<(), String> {
+        for node in nodes {
+            self.exec(node)?;
+        }
+        Ok(())
+    }
+
+    fn exec(&mut self, node: &ASTNode) -> Result<(), String> {
+        match node {
+            ASTNode::AIModelFrom(name) => match self.variables.get(name) {
+                Some(path) if !path.trim().is_empty() => {
+                    let path = path.trim().to_string();
+                    self.exec(&ASTNode::AIModel(path))?;
                 }
-                ASTNode::SetVarFromAI(name, prompt) => {
-                    // If the model is missing or prediction fails, store the prompt as-is.
-                    match &self.ai_model {
-                        Some(m) => match m.predict(&prompt) {
-                            Ok(pred) => {
-                                self.variables.insert(name.clone(), pred.trim().to_string());
-                            }
-                            Err(_) => {
-                                self.variables
-                                    .insert(name.clone(), prompt.trim().to_string());
-                            }
-                        },
-                        None => {
-                            self.variables
-                                .insert(name.clone(), prompt.trim().to_string());
-                        }
+                _ => {
+                    self.ai_model = None;
+                    self.ai_model_path = None;
+                    self.emit_neuro(&format!(
+                        "❌ AI: variable '{name}' is not set (expected a model path)"
+                    ));
+                }
+            },
+            ASTNode::AIModel(path) => {
+                let path = &interpolate(path, &self.variables);
+                // A bad path is reported and the script goes on without an active model, so
+                // `set x from AI:` falls back to its backups / default / the prompt.
+                let model = match AIModel::load_cached(path) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        let msg = if std::path::Path::new(path).exists() {
+                            format!("❌ Failed to load model {path}: {e}")
+                        } else {
+                            format!("❌ Model not found: {path}")
+                        };
+                        append_log(&format!("model load error: {e}"));
+                        self.ai_model = None;
+                        self.ai_model_path = None;
+                        self.emit_neuro(&msg);
+                        return Ok(());
                     }
+                };
+                if self.echo {
+                    println!("✅ Model loaded: {path}");
+                } else {
+                    eprintln!("✅ Model loaded: {path}");
+                }
+                self.models.insert(model.kind(), model.clone());
+                if model.kind() == ModelKind::MacroIntent {
+                    self.macro_model = Some(model.clone());
+                }
+                let active = self.ai_model.as_ref().map(AIModel::kind);
+                if becomes_active_model(model.kind(), active) {
+                    self.ai_model = Some(model);
+                    self.ai_model_path = Some(path.clone());
                 }
+            }
 
-                ASTNode::MacroCall(instr) => {
-                    let instr_low = instr.to_ascii_lowercase();
-                    if instr_low.contains("main starts here using //") {
-                        let dsl = r#"neuro "// main starts here""#;
-                        append_raw_log("DSL", dsl);
-                        match tokenize(dsl).map(parse_nodes) {
-                            Ok(ast2) => self.run(ast2),
-                            Err(e) => eprintln!("❌ Macro execution failed: {e}"),
-                        }
-                        continue;
+            // `# @model sst2` is `AI:` with the path the ID resolves to. Other directives are
+            // still just comments.
+            ASTNode::Directive { name, arg } if name == "model" => {
+                let path = resolve_model_path(&models_root(), &arg.to_ascii_lowercase())
+                    .ok_or_else(|| format!("❌ Unknown model id '{arg}' in # @model"))?;
+                self.exec(&ASTNode::AIModel(path))?;
+            }
+            ASTNode::Directive { .. } => {}
+            ASTNode::Line(n) => self.current_line = Some(*n),
+
+            ASTNode::Neuro(expr) => {
+                let msg = self.neuro_text(expr);
+                self.budget.output(msg.len())?;
+                self.emit_neuro(&msg);
+            }
+            ASTNode::NeuroRaw(expr) => {
+                let msg = self.neuro_text(expr);
+                self.budget.output(msg.len())?;
+                self.emit_neuro_raw(&msg);
+            }
+
+            ASTNode::SetVar(name, expr) => {
+                self.check_variable_budget(name)?;
+                match self.eval_value(expr) {
+                    list @ Value::List(_) => {
+                        self.variables.insert_value(name.clone(), list);
                     }
-                    let prompt_raw = prepare_prompt(&instr);
-                    if prompt_raw.to_ascii_lowercase().contains("main starts here") {
-                        let dsl = r#"neuro "// main starts here""#;
-                        append_raw_log("DSL", dsl);
-                        match tokenize(dsl).map(parse_nodes) {
-                            Ok(ast2) => self.run(ast2),
-                            Err(e) => eprintln!("❌ Macro execution failed: {e}"),
-                        }
-                        continue;
+                    other => {
+                        let val = other.to_string().trim().to_string();
+                        self.variables.insert(name.clone(), val);
                     }
-                    if prompt_raw
-                        .to_ascii_lowercase()
-                        .contains("main starts here using //")
-                    {
-                        let dsl = r#"neuro "// main starts here""#;
-                        append_raw_log("DSL", dsl);
-                        match tokenize(dsl).map(parse_nodes) {
-                            Ok(ast2) => self.run(ast2),
-                            Err(e) => eprintln!("❌ Macro execution failed: {e}"),
+                }
+            }
+            ASTNode::SetVarFromAI {
+                var,
+                prompt,
+                model,
+                backups,
+                default,
+            } => {
+                self.check_variable_budget(var)?;
+                // The inline model (or else the active one) first, then each `else from AI`
+                // backup in order; if none of them loads and predicts, store the `else "..."`
+                // default, or the prompt as-is without one. An inline model is used for this
+                // statement only.
+                let mut value = match model {
+                    Some(spec) => {
+                        // A model of that kind loaded with `AI:` wins over the default path.
+                        let loaded = ModelKind::from_id(&spec.to_ascii_lowercase())
+                            .and_then(|kind| self.models.get(&kind));
+                        match loaded {
+                            Some(m) => m.predict(prompt).ok(),
+                            None => {
+                                let path = inline_model_path(spec);
+                                match AIModel::load_cached(&path) {
+                                    Ok(m) => m.predict(prompt).ok(),
+                                    Err(e) => {
+                                        append_log(&format!(
+                                            "inline model {path} unavailable: {e}"
+                                        ));
+                                        None
+                                    }
+                                }
+                            }
                         }
-                        continue;
                     }
-                    let prompt = strip_wrapping_quotes(&prompt_raw);
-                    if prompt
-                        .to_ascii_lowercase()
-                        .contains("main starts here using //")
-                    {
-                        let dsl = "// main starts here";
-                        append_raw_log("DSL", dsl);
-                        match tokenize(dsl).map(parse_nodes) {
-                            Ok(ast2) => self.run(ast2),
-                            Err(e) => eprintln!("❌ Macro execution failed: {e}"),
-                        }
-                        continue;
+                    None => self.ai_model.as_ref().and_then(|m| m.predict(prompt).ok()),
+                };
+                for path in backups {
+                    if value.is_some() {
+                        break;
                     }
-                    let threshold = macro_intent_threshold();
-
-                    let mut label = "Unknown".to_string();
-                    let mut score = 0.0f32;
-
-                    if let Some(model) = self.ensure_macro_model() {
-                        match model.predict_with_score(&prompt) {
-                            Ok((l, s)) => {
-                                label = l;
-                                score = s;
-                            }
-                            Err(e) => eprintln!("⚠️ Macro model classification failed: {e}"),
-                        }
-                    } else {
-                        eprintln!("⚠️ Macro model is not loaded; running fallback.");
+                    match AIModel::load_cached(path) {
+                        Ok(m) => value = m.predict(prompt).ok(),
+                        Err(e) => append_log(&format!("backup model {path} unavailable: {e}")),
                     }
+                }
+                let value = match (value, default) {
+                    (Some(v), Some(d)) if v.trim().is_empty() => d.clone(),
+                    (Some(v), _) => v,
+                    (None, Some(d)) => d.clone(),
+                    (None, None) => prompt.clone(),
+                };
+                self.variables.insert(var.clone(), value.trim().to_string());
+            }
 
-                    append_raw_log(
-                        "INTENT",
-                        &format!("label={label} score={score:.3} | {prompt}"),
-                    );
-
-                    let mut label_for_template = if score >= threshold {
-                        label.as_str()
-                    } else {
-                        infer_label_from_prompt(&prompt)
-                    };
+            ASTNode::MacroCall(instr) => {
+                let dsl = self.macro_to_dsl(instr)?;
 
-                    let plow = prompt.to_ascii_lowercase();
-                    let is_loopish = looks_like_loop_prompt(prompt.as_str());
-                    // Prevent obvious false loop matches.
-                    if label_for_template == "Loop" && plow.trim_start().starts_with("if ") {
-                        label_for_template = "Branch";
-                    } else if label_for_template == "Loop" && !is_loopish {
-                        label_for_template = infer_label_from_prompt(&prompt);
+                match tokenize(&dsl).map(parse_nodes) {
+                    Ok(ast2) => self.run(ast2)?,
+                    Err(e) => {
+                        eprintln!("❌ Macro execution failed: {e}");
+                        append_log(&format!("macro error: {e}"));
                     }
+                }
+            }
 
-                    // Prefer SetVar/Arith for set/create/store prompts.
-                    let plow_trim = plow.trim_start();
-                    let has_embedded_set = embedded_set_re().is_match(prompt.as_str());
-                    if plow_trim.starts_with("set ")
-                        || plow_trim.starts_with("create ")
-                        || plow_trim.starts_with("store ")
-                        || has_embedded_set
-                    {
-                        // Detect "math" primarily from the RHS expression, not the whole prompt
-                        // (e.g. `set greeting = 'Hi' ... print greeting + ' ' + target` is not Arith).
-                        let has_math = if let Some((_v, expr, _)) = parse_var_expr(&prompt) {
-                            let e = expr.to_ascii_lowercase();
-                            e.contains('+')
-                                || e.contains('-')
-                                || e.contains('*')
-                                || e.contains('/')
-                                || e.contains('%')
-                                || e.contains(" plus ")
-                                || e.contains(" minus ")
-                        } else {
-                            plow.contains('+')
-                                || plow.contains('-')
-                                || plow.contains('*')
-                                || (plow.contains('/') && !plow.contains("//"))
-                                || plow.contains('%')
-                                || plow.contains(" plus ")
-                                || plow.contains(" minus ")
-                        };
-                        label_for_template = if has_math { "Arith" } else { "SetVar" };
+            ASTNode::IfStatement {
+                condition,
+                body,
+                elif_blocks,
+                else_body,
+            } => {
+                if self.eval_bool(condition) {
+                    return self.run_block(body);
+                }
+                for (c, blk) in elif_blocks {
+                    if self.eval_bool(c) {
+                        return self.run_block(blk);
                     }
+                }
+                if let Some(blk) = else_body {
+                    self.run_block(blk)?;
+                }
+            }
 
-                    // Prefer Concat when the prompt clearly asks to join/concat quoted literals.
-                    let has_concat_word = plow.contains("combine")
-                        || plow.contains("join")
-                        || plow.contains("concat")
-                        || plow.contains("concatenate");
-                    if has_concat_word && all_quoted(&prompt).len() >= 2 {
-                        label_for_template = "Concat";
+            ASTNode::WhileStatement { condition, body } => {
+                let mut iterations = 0usize;
+                while self.eval_bool(condition) {
+                    iterations += 1;
+                    if iterations > MAX_LOOP_ITERATIONS {
+                        return Err(format!(
+                            "❌ while loop exceeded {MAX_LOOP_ITERATIONS} iterations; aborting"
+                        ));
                     }
+                    self.loop_iteration()?;
+                    self.run_block(body)?;
+                }
+            }
 
-                    // Prefer DocPrint for comment macros when there is no assignment.
-                    let has_assignment = plow.contains("set ")
-                        || plow.contains("create ")
-                        || plow.contains("store ");
-                    let is_comment_instruction = plow.contains("write a comment")
-                        || plow.contains("add comment")
-                        || plow.contains("insert comment")
-                        || plow.contains("comment that says")
-                        || plow.contains("comment says")
-                        || plow.contains("using //")
-                        || plow.contains("using #");
-                    if is_comment_instruction && !has_assignment {
-                        label_for_template = "DocPrint";
-                    }
+            ASTNode::ForRange {
+                var,
+                start,
+                end,
+                body,
+            } => {
+                let bound = |raw: String| {
+                    raw.trim().parse::<i64>().map_err(|_| {
+                        format!("❌ for range bounds must be integers, got '{}'", raw.trim())
+                    })
+                };
+                let from = bound(self.eval_expr(start))?;
+                let to = bound(self.eval_expr(end))?;
+                if to.saturating_sub(from) > MAX_LOOP_ITERATIONS as i64 {
+                    return Err(format!(
+                        "❌ for loop range({from}, {to}) exceeds {MAX_LOOP_ITERATIONS} iterations"
+                    ));
+                }
 
-                    // Prefer DocPrint for simple print/say/output/echo/display/format prompts.
-                    let starts_docprint = plow_trim.starts_with("print ")
-                        || plow_trim.starts_with("output ")
-                        || plow_trim.starts_with("echo ")
-                        || plow_trim.starts_with("say ")
-                        || plow_trim.starts_with("display ")
-                        || plow_trim.starts_with("format ");
-                    if starts_docprint && !has_assignment && !is_loopish {
-                        label_for_template = "DocPrint";
-                    }
+                self.run_counted(var, from..to, body)?;
+            }
 
-                    let mut dsl = build_macro_dsl(label_for_template, &prompt);
-                    dsl = dsl.replace('\'', "\"");
-                    if dsl.trim().is_empty() {
-                        dsl = neuro_line(&prompt);
-                    }
-                    append_raw_log("DSL", &dsl);
+            ASTNode::ParseError(msg) => return Err(msg.clone()),
 
-                    match tokenize(&dsl).map(parse_nodes) {
-                        Ok(ast2) => self.run(ast2),
-                        Err(e) => {
-                            eprintln!("❌ Macro execution failed: {e}");
-                            append_log(&format!("macro error: {e}"));
-                        }
-                    }
-                }
+            ASTNode::Repeat { count, body } => {
+                // Clamped rather than rejected: `repeat` is the "just do it N times" loop.
+                let n = (*count).clamp(0, MAX_LOOP_ITERATIONS as i64);
+                self.run_counted("__iter", 0..n, body)?;
+            }
+        }
+        Ok(())
+    }
 
-                ASTNode::IfStatement {
-                    condition,
-                    body,
-                    elif_blocks,
-                    else_body,
-                } => {
-                    if self.eval_bool(&condition) {
-                        for s in body {
-                            self.run(vec![s]);
-                        }
-                        continue;
-                    }
-                    let mut matched = false;
-                    for (c, blk) in elif_blocks {
-                        if self.eval_bool(&c) {
-                            for s in blk {
-                                self.run(vec![s]);
-                            }
-                            matched = true;
-                            break;
-                        }
-                    }
-                    if !matched {
-                        if let Some(blk) = else_body {
-                            for s in blk {
-                                self.run(vec![s]);
-                            }
-                        }
-                    }
+    /// Charges one loop iteration to the budget and checks the deadline.
+    fn loop_iteration(&mut self) -> Result<(), String> {
+        self.budget.iteration()?;
+        match self.deadline {
+            Some(at) if Instant::now() >= at => Err("❌ Time limit reached; aborting".into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs `body` once per value, binding it to `var`. The loop variable is scoped to the loop:
+    /// it is restored (or dropped) afterwards, also when the body fails.
+    fn run_counted(
+        &mut self,
+        var: &str,
+        values: std::ops::Range<i64>,
+        body: &[ASTNode],
+    ) -> Result<(), String> {
+        let saved = self.variables.value(var).cloned();
+        let mut result = Ok(());
+        for i in values {
+            self.variables.insert(var.to_string(), i.to_string());
+            result = self.loop_iteration();
+            if result.is_ok() {
+                result = self.run_block(body);
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+        match saved {
+            Some(v) => {
+                self.variables.insert_value(var.to_string(), v);
+            }
+            None => {
+                self.variables.remove(var);
+            }
+        }
+        result
+    }
+
+    /// Refuses a new variable name once the budget's variable limit is reached.
+    fn check_variable_budget(&self, name: &str) -> Result<(), String> {
+        if self.variables.contains_key(name) {
+            return Ok(());
+        }
+        self.budget.new_variable(self.variables.len())
+    }
+
+    /*---------------------- eval_value --------------------*/
+    /// Typed evaluation for the places where lists matter; everything else goes through
+    /// `eval_expr` and comes back as text.
+    fn eval_value(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::List(items) => Value::List(items.iter().map(|e| self.eval_value(e)).collect()),
+            Expr::Index(base, index) => {
+                let base = self.eval_value(base);
+                let index = self.eval_expr(index);
+                let Some(items) = base.as_list() else {
+                    return Value::Str(format!("❌ Cannot index into '{base}': not a list"));
+                };
+                match index.trim().parse::<usize>() {
+                    Ok(i) if i < items.len() => items[i].clone(),
+                    Ok(i) => Value::Str(format!(
+                        "❌ Index {i} out of range for a list of length {}",
+                        items.len()
+                    )),
+                    Err(_) => Value::Str(format!(
+                        "❌ List index must be a non-negative integer, got '{}'",
+                        index.trim()
+                    )),
+                }
+            }
+            // First arm equal to the subject (same rule as `==`); no match and no default gives "".
+            Expr::Match {
+                subject,
+                arms,
+                default,
+            } => {
+                let subject = self.eval_expr(subject);
+                arms.iter()
+                    .find(|(pattern, _)| eq_case(&subject, &self.eval_expr(pattern)))
+                    .map(|(_, value)| value)
+                    .or(default.as_deref())
+                    .map_or_else(|| Value::Str(String::new()), |e| self.eval_value(e))
+            }
+            Expr::Value(v) if !v.starts_with(|c: char| c.is_ascii_digit()) => {
+                match self.variables.value(v) {
+                    Some(list @ Value::List(_)) => list.clone(),
+                    _ => Value::from_text(self.eval_expr(expr)),
                 }
             }
+            Expr::Call(name, args) if name == "split" => {
+                let args: Vec<String> = args.iter().map(|a| self.eval_expr(a)).collect();
+                builtins::split(&args)
+            }
+            _ => Value::from_text(self.eval_expr(expr)),
+        }
+    }
+
+    /// Text of a `neuro` / `neuro_raw` argument. A bare string literal is printed verbatim
+    /// apart from `{var}` interpolation; anything else is evaluated (undefined identifiers fall
+    /// back to their own name) and trimmed.
+    fn neuro_text(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::StringLit(s) => interpolate(s, &self.variables),
+            _ => self.eval_expr(expr).trim().to_string(),
         }
     }
 
@@ -628,13 +1083,16 @@ impl Interpreter {
     fn eval_expr(&self, expr: &Expr) -> String {
         match expr {
             Expr::StringLit(s) => s.clone(),
+            Expr::List(_) | Expr::Index(..) | Expr::Match { .. } => {
+                self.eval_value(expr).to_string()
+            }
+            Expr::Bool(b) => b.to_string(),
             Expr::Value(v) => {
                 if v.parse::<i64>().is_ok() {
                     return v.clone();
                 }
-                match v.as_str() {
-                    "None" | "true" | "false" => return v.clone(),
-                    _ => {}
+                if v == "None" {
+                    return v.clone();
                 }
                 // If the name is not a variable, treat it as a literal.
                 self.variables.get(v).cloned().unwrap_or_else(|| v.clone())
@@ -659,16 +1117,63 @@ impl Interpreter {
                     BinaryOperator::Sub => num(|a, b| a - b),
                     BinaryOperator::Mul => num(|a, b| a * b),
                     BinaryOperator::Div => num(|a, b| if b != 0.0 { a / b } else { f64::NAN }),
+                    // Floors toward negative infinity: `-7 div 2` is `-4`, not `-3`.
+                    BinaryOperator::FloorDiv => {
+                        num(|a, b| if b != 0.0 { (a / b).floor() } else { f64::NAN })
+                    }
+                    // 0 ** negative is a division by zero: NaN, same as `/`.
+                    BinaryOperator::Pow => num(|a, b| {
+                        if a == 0.0 && b < 0.0 {
+                            f64::NAN
+                        } else {
+                            a.powf(b)
+                        }
+                    }),
                     BinaryOperator::Mod => match (l.parse::<i64>(), r.parse::<i64>()) {
                         (Ok(a), Ok(b)) => format!("{}", a % b),
                         _ => "❌ Modulo does not work on strings".into(),
                     },
+                    BinaryOperator::BitAnd
+                    | BinaryOperator::BitOr
+                    | BinaryOperator::BitXor
+                    | BinaryOperator::Shl
+                    | BinaryOperator::Shr => bitwise(op, l, r),
                     BinaryOperator::Gt => format!("{}", l > r),
                     BinaryOperator::Lt => format!("{}", l < r),
                     BinaryOperator::Ge => format!("{}", l >= r),
                     BinaryOperator::Le => format!("{}", l <= r),
                     BinaryOperator::Eq => format!("{}", eq_case(l, r)),
                     BinaryOperator::Ne => format!("{}", !eq_case(l, r)),
+                    BinaryOperator::And => {
+                        format!("{}", builtins::truthy(l) && builtins::truthy(r))
+                    }
+                    BinaryOperator::Or => format!("{}", builtins::truthy(l) || builtins::truthy(r)),
+                }
+            }
+            Expr::Call(name, args) => {
+                // `len` counts list items; on anything else it counts characters.
+                if name == "len" && args.len() == 1 {
+                    if let Value::List(items) = self.eval_value(&args[0]) {
+                        return items.len().to_string();
+                    }
+                }
+                // `split` builds a list and `join` / `concat_list` take one, so they need the
+                // typed value.
+                if name == "split" {
+                    return self.eval_value(expr).to_string();
+                }
+                if name == "join" || name == "concat_list" {
+                    return match args.as_slice() {
+                        [list, sep] => {
+                            builtins::join(name, &self.eval_value(list), &self.eval_expr(sep))
+                        }
+                        _ => format!("❌ {name}() takes 2 arguments, got {}", args.len()),
+                    };
+                }
+                let args: Vec<String> = args.iter().map(|a| self.eval_expr(a)).collect();
+                match name.as_str() {
+                    "param" => self.param(&args),
+                    _ => builtins::call(name, &args, &self.rng, self.clock.as_ref()),
                 }
             }
         }
@@ -716,9 +1221,152 @@ impl Interpreter {
             BoolExpr::LessEqual(l, r) => rel(l, r, |o| o == Ordering::Less || o == Ordering::Equal),
             BoolExpr::And(l, r) => self.eval_bool(l) && self.eval_bool(r),
             BoolExpr::Or(l, r) => self.eval_bool(l) || self.eval_bool(r),
+            BoolExpr::Truthy(v) => {
+                // Quoted, numeric and boolean literals stand for themselves; an undefined variable
+                // is false.
+                let value = if v.starts_with('"') && v.ends_with('"') && v.len() >= 2 {
+                    v.trim_matches('"').to_string()
+                } else if let Some(val) = vars.get(v) {
+                    val.clone()
+                } else if v.parse::<f64>().is_ok() || v == "true" || v == "false" {
+                    v.clone()
+                } else {
+                    String::new()
+                };
+                builtins::truthy(&value)
+            }
+            BoolExpr::Not(inner) => !self.eval_bool(inner),
+            BoolExpr::Predicate(name, args) => {
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|a| {
+                        if a.starts_with('"') && a.ends_with('"') && a.len() >= 2 {
+                            a.trim_matches('"').to_string()
+                        } else {
+                            var_or_literal(vars, a)
+                        }
+                    })
+                    .collect();
+                // The parser rejects unknown names and wrong arity; a hand-built AST gets false.
+                builtins::predicate(name, &args).unwrap_or(false)
+            }
         }
     }
 
+    /// Turns a `macro from AI:` prompt into DSL text without running it: MacroIntent label
+    /// (or the keyword heuristics when the model is missing / unsure), then the template.
+    pub fn macro_to_dsl(&mut self, instr: &str) -> Result<String, String> {
+        let limit = macro_prompt_limit();
+        let len = instr.chars().count();
+        if len > limit {
+            append_log(&format!("macro error: prompt too long ({len} chars)"));
+            return Err(format!(
+                "❌ Macro prompt is {len} characters; the limit is {limit} (NC_MAX_MACRO_PROMPT)"
+            ));
+        }
+        // Resolved once per prompt: every "and then" step shares the model, so a missing
+        // model is reported once rather than per step.
+        let model = self.ensure_macro_model();
+        if model.is_none() {
+            eprintln!("⚠️ Macro model is not loaded; running fallback.");
+        }
+        Self::prompt_to_dsl(instr, model.as_ref())
+    }
+
+    /// One prompt (or one step of a sequence) to DSL text, classified with `model` when there
+    /// is one and with the keyword heuristics otherwise.
+    fn prompt_to_dsl(instr: &str, model: Option<&AIModel>) -> Result<String, String> {
+        let instr_low = instr.to_ascii_lowercase();
+        if instr_low.contains("main starts here using //") {
+            let dsl = r#"neuro "// main starts here""#;
+            append_raw_log("DSL", dsl);
+            return Ok(dsl.to_string());
+        }
+        let prompt_raw = prepare_prompt(instr);
+        if prompt_raw.to_ascii_lowercase().contains("main starts here") {
+            let dsl = r#"neuro "// main starts here""#;
+            append_raw_log("DSL", dsl);
+            return Ok(dsl.to_string());
+        }
+        if prompt_raw
+            .to_ascii_lowercase()
+            .contains("main starts here using //")
+        {
+            let dsl = r#"neuro "// main starts here""#;
+            append_raw_log("DSL", dsl);
+            return Ok(dsl.to_string());
+        }
+        let prompt = strip_wrapping_quotes(&prompt_raw);
+        if prompt
+            .to_ascii_lowercase()
+            .contains("main starts here using //")
+        {
+            let dsl = "// main starts here";
+            append_raw_log("DSL", dsl);
+            return Ok(dsl.to_string());
+        }
+        // "set x to 5 and then say Done then say Bye": one template per step, in order.
+        let steps = split_sequence(&prompt);
+        if steps.len() > 1 {
+            let mut lines: Vec<String> = Vec::with_capacity(steps.len());
+            for step in &steps {
+                // "... then show x" after a step that set x prints the variable, not the word.
+                let printed_var = step_print_target(step).filter(|var| {
+                    let set_line = format!("set {var} =");
+                    lines
+                        .iter()
+                        .any(|l| l.lines().any(|l| l.starts_with(&set_line)))
+                });
+                match printed_var {
+                    Some(var) => lines.push(format!("neuro {var}")),
+                    None => lines.push(Self::prompt_to_dsl(step, model)?),
+                }
+            }
+            let dsl = lines.join("\n");
+            append_raw_log("DSL", &dsl);
+            return Ok(dsl);
+        }
+
+        let threshold = macro_intent_threshold();
+
+        let mut label = "Unknown".to_string();
+        let mut score = 0.0f32;
+
+        if let Some(model) = model {
+            match model.predict_with_score(&prompt) {
+                Ok((l, s)) => {
+                    label = l;
+                    score = s;
+                }
+                Err(e) => eprintln!("⚠️ Macro model classification failed: {e}"),
+            }
+        }
+
+        append_raw_log(
+            "INTENT",
+            &format!("label={label} score={score:.3} | {prompt}"),
+        );
+
+        let base_label = if score >= threshold {
+            label.as_str()
+        } else {
+            infer_label_from_prompt(&prompt)
+        };
+        let label_for_template = match override_macro_label(&prompt, base_label) {
+            Some((_, overridden)) => overridden,
+            None => base_label,
+        };
+
+        let mut dsl = build_macro_dsl(label_for_template, &prompt);
+        dsl = dsl.replace('\'', "\"");
+        // An empty Loop template is a deliberate zero-count loop, not a miss.
+        if dsl.trim().is_empty() && label_for_template != "Loop" {
+            dsl = neuro_line(&prompt);
+        }
+        append_raw_log("DSL", &dsl);
+        Ok(dsl)
+    }
+
     fn ensure_macro_model(&mut self) -> Option<AIModel> {
         if let Some(m) = &self.macro_model {
             return Some(m.clone());
@@ -731,7 +1379,7 @@ impl Interpreter {
             }
         }
         let path = macro_model_path();
-        match AIModel::new(&path) {
+        match AIModel::load_cached(&path) {
             Ok(mdl) => {
                 self.macro_model = Some(mdl.clone());
                 Some(mdl)
@@ -744,6 +1392,69 @@ impl Interpreter {
     }
 }
 
+/// Splits a macro prompt on `and then` / `then` (outside quotes) into ordered steps.
+/// A step starting with `if` keeps everything after it: its `then` belongs to the branch.
+fn split_sequence(prompt: &str) -> Vec<String> {
+    let mut steps = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut in_quote = None;
+    let words: Vec<&str> = prompt.split_whitespace().collect();
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        let first_is_if = current
+            .first()
+            .is_some_and(|w| w.eq_ignore_ascii_case("if"));
+        if in_quote.is_none() && !first_is_if && !current.is_empty() {
+            let sep = if word.eq_ignore_ascii_case("then") {
+                Some(1)
+            } else if word.eq_ignore_ascii_case("and")
+                && words
+                    .get(i + 1)
+                    .is_some_and(|w| w.eq_ignore_ascii_case("then"))
+            {
+                Some(2)
+            } else {
+                None
+            };
+            if let Some(skip) = sep {
+                steps.push(current.join(" "));
+                current.clear();
+                i += skip;
+                continue;
+            }
+        }
+        for ch in word.chars().filter(|c| matches!(c, '"' | '\'')) {
+            in_quote = match in_quote {
+                None => Some(ch),
+                Some(q) if q == ch => None,
+                other => other,
+            };
+        }
+        current.push(word);
+        i += 1;
+    }
+    if !current.is_empty() {
+        steps.push(current.join(" "));
+    }
+    steps
+}
+
+/// `print x` / `show x` / `say x` ... with a bare identifier: the identifier.
+fn step_print_target(step: &str) -> Option<&str> {
+    let (verb, rest) = step.trim().split_once(' ')?;
+    let verb = verb.to_ascii_lowercase();
+    let is_print = matches!(
+        verb.as_str(),
+        "print" | "show" | "say" | "display" | "output" | "echo"
+    );
+    let var = rest.trim();
+    let is_ident = !var.is_empty()
+        && var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !var.starts_with(|c: char| c.is_ascii_digit());
+    (is_print && is_ident).then_some(var)
+}
+
 /* ----------------------------- DSL‑strip helper ---------------------- */
 #[allow(dead_code)]
 fn extract_dsl(src: &str) -> String {
@@ -841,8 +1552,8 @@ fn build_macro_dsl(label: &str, prompt: &str) -> String {
 fn build_loop_dsl(prompt: &str) -> String {
     let prompt = strip_wrapping_quotes(prompt);
     let msg = loop_message_from_prompt(prompt.as_str());
-    let times = loop_count_from_prompt(prompt.as_str()).unwrap_or(1);
-    let count = times.clamp(1, 12);
+    // An explicit 0 (or a clamped negative count) means "print nothing".
+    let count = loop_count_from_prompt(prompt.as_str()).unwrap_or(1).min(12);
     (0..count)
         .map(|_| format!("neuro \"{msg}\""))
         .collect::<Vec<_>>()
@@ -892,7 +1603,7 @@ fn build_setvar_dsl(prompt: &str) -> String {
         )
         .unwrap();
         if let Some(c) = re_and_assign.captures(&prompt) {
-            let var2 = c.get(1).map(|m| m.as_str().to_string()).unwrap_or_default();
+            let var2 = c.get(1).map(|m| safe_var(m.as_str())).unwrap_or_default();
             let expr2 = c.get(2).map(|m| m.as_str()).unwrap_or("").trim();
             if !var2.is_empty() && var2 != var && !expr2.is_empty() {
                 let rhs2 = normalize_expr(expr2);
@@ -918,14 +1629,14 @@ fn build_concat_dsl(prompt: &str) -> String {
     let var = Regex::new(r"(?i)(?:into|to)\s+([A-Za-z_][\w]*)")
         .unwrap()
         .captures(&prompt)
-        .and_then(|c| c.get(1).map(|m| m.as_str().to_string()))
+        .and_then(|c| c.get(1).map(|m| safe_var(m.as_str())))
         .unwrap_or_else(|| "result".to_string());
 
     // Special-case: "Concatenate name and score ... store in result"
     if let Some(c) = Regex::new(r"(?is)^\s*concatenate\s+([A-Za-z_][\w]*)\s+(?:and\s+)?([A-Za-z_][\w]*).*store\s+in\s+([A-Za-z_][\w]*)").unwrap().captures(&prompt) {
         let a = c.get(1).map(|m| m.as_str()).unwrap_or("a");
         let b = c.get(2).map(|m| m.as_str()).unwrap_or("b");
-        let target = c.get(3).map(|m| m.as_str()).unwrap_or(var.as_str());
+        let target = c.get(3).map(|m| safe_var(m.as_str())).unwrap_or(var.clone());
         let mut lines = vec![format!("set {target} = {a} + {b}")];
         if mentions_print(&prompt) || prompt.to_ascii_lowercase().contains("print") {
             lines.push(format!("neuro {target}"));
@@ -969,7 +1680,10 @@ fn build_arith_dsl(prompt: &str) -> String {
             c.get(1).map(|m| m.as_str()).unwrap_or("a+b"),
             c.get(2).map(|m| m.as_str()).unwrap_or("1")
         );
-        let var = c.get(3).map(|m| m.as_str()).unwrap_or("result");
+        let var = c
+            .get(3)
+            .map(|m| safe_var(m.as_str()))
+            .unwrap_or("result".into());
         return format!("set {var} = {expr}");
     }
     // "Subtract y from x, divide by 4, store in q" (tolerant parsing)
@@ -990,8 +1704,8 @@ fn build_arith_dsl(prompt: &str) -> String {
             let target = re_store
                 .captures(&prompt)
                 .and_then(|s| s.get(1))
-                .map(|m| m.as_str())
-                .unwrap_or("result");
+                .map(|m| safe_var(m.as_str()))
+                .unwrap_or("result".into());
             let rhs = if div == "1" {
                 format!("{} - {}", minuend, subtrahend)
             } else {
@@ -1038,7 +1752,138 @@ fn build_arith_dsl(prompt: &str) -> String {
     build_setvar_dsl(&prompt)
 }
 
-fn infer_label_from_prompt(prompt: &str) -> &str {
+/// What a macro label-override rule looks at.
+struct LabelInput<'a> {
+    prompt: &'a str,
+    lower: String,
+    /// The classifier's label, or the keyword fallback's below the threshold.
+    base: &'a str,
+}
+
+impl LabelInput<'_> {
+    fn trimmed(&self) -> &str {
+        self.lower.trim_start()
+    }
+
+    /// Mentions `set` / `create` / `store` anywhere; keeps the DocPrint rules off prompts that
+    /// also assign.
+    fn has_assignment(&self) -> bool {
+        self.lower.contains("set ")
+            || self.lower.contains("create ")
+            || self.lower.contains("store ")
+    }
+}
+
+type LabelRule = fn(&LabelInput) -> Option<&'static str>;
+
+/// Overrides for the `macro from AI:` template label, highest precedence first. The first rule
+/// that returns a label wins; when none does, the classifier's label stands.
+const MACRO_LABEL_RULES: &[(&str, LabelRule)] = &[
+    ("print_prefix", print_prefix_rule),
+    ("comment", comment_rule),
+    ("quoted_concat", quoted_concat_rule),
+    ("assignment", assignment_rule),
+    ("loop_false_positive", loop_false_positive_rule),
+];
+
+/// The first matching rule of [`MACRO_LABEL_RULES`] as `(rule name, label)`.
+fn override_macro_label(prompt: &str, base: &str) -> Option<(&'static str, &'static str)> {
+    let input = LabelInput {
+        prompt,
+        lower: prompt.to_ascii_lowercase(),
+        base,
+    };
+    MACRO_LABEL_RULES
+        .iter()
+        .find_map(|(name, rule)| rule(&input).map(|label| (*name, label)))
+}
+
+/// `print ...` / `say ...` / `echo ...` (and output/display/format) print, unless the prompt
+/// also assigns or reads as a loop.
+fn print_prefix_rule(input: &LabelInput) -> Option<&'static str> {
+    let t = input.trimmed();
+    let starts_docprint = ["print ", "output ", "echo ", "say ", "display ", "format "]
+        .iter()
+        .any(|word| t.starts_with(word));
+    (starts_docprint && !input.has_assignment() && !looks_like_loop_prompt(input.prompt))
+        .then_some("DocPrint")
+}
+
+/// "write a comment ...", "... using //" and the like, unless the prompt also assigns.
+fn comment_rule(input: &LabelInput) -> Option<&'static str> {
+    let is_comment_instruction = [
+        "write a comment",
+        "add comment",
+        "insert comment",
+        "comment that says",
+        "comment says",
+        "using //",
+        "using #",
+    ]
+    .iter()
+    .any(|phrase| input.lower.contains(phrase));
+    (is_comment_instruction && !input.has_assignment()).then_some("DocPrint")
+}
+
+/// join/combine/concat with at least two quoted literals.
+fn quoted_concat_rule(input: &LabelInput) -> Option<&'static str> {
+    let has_concat_word = ["combine", "join", "concat"]
+        .iter()
+        .any(|word| input.lower.contains(word));
+    (has_concat_word && all_quoted(input.prompt).len() >= 2).then_some("Concat")
+}
+
+/// Prompts starting with set/create/store (or with an embedded `set x = ...`) assign: `Arith`
+/// when the value has math in it, else `SetVar`.
+fn assignment_rule(input: &LabelInput) -> Option<&'static str> {
+    let t = input.trimmed();
+    let assigns = t.starts_with("set ")
+        || t.starts_with("create ")
+        || t.starts_with("store ")
+        || embedded_set_re().is_match(input.prompt);
+    if !assigns {
+        return None;
+    }
+    // Detect "math" primarily from the RHS expression, not the whole prompt
+    // (e.g. `set greeting = 'Hi' ... print greeting + ' ' + target` is not Arith).
+    let has_math = if let Some((_v, expr, _)) = parse_var_expr(input.prompt) {
+        let e = expr.to_ascii_lowercase();
+        e.contains('+')
+            || e.contains('-')
+            || e.contains('*')
+            || e.contains('/')
+            || e.contains('%')
+            || e.contains(" plus ")
+            || e.contains(" minus ")
+    } else {
+        let p = &input.lower;
+        p.contains('+')
+            || p.contains('-')
+            || p.contains('*')
+            || (p.contains('/') && !p.contains("//"))
+            || p.contains('%')
+            || p.contains(" plus ")
+            || p.contains(" minus ")
+    };
+    Some(if has_math { "Arith" } else { "SetVar" })
+}
+
+/// A `Loop` label on a prompt that doesn't read as a loop: `if ...` is a `Branch`, anything
+/// else gets the keyword fallback's label.
+fn loop_false_positive_rule(input: &LabelInput) -> Option<&'static str> {
+    if input.base != "Loop" {
+        return None;
+    }
+    if input.trimmed().starts_with("if ") {
+        Some("Branch")
+    } else if !looks_like_loop_prompt(input.prompt) {
+        Some(infer_label_from_prompt(input.prompt))
+    } else {
+        None
+    }
+}
+
+fn infer_label_from_prompt(prompt: &str) -> &'static str {
     let p = prompt.to_ascii_lowercase();
     if looks_like_loop_prompt(prompt) {
         return "Loop";
@@ -1092,20 +1937,33 @@ fn infer_label_from_prompt(prompt: &str) -> &str {
 fn loop_count_from_prompt(prompt: &str) -> Option<usize> {
     let p = strip_wrapping_quotes(prompt);
 
+    // A leading '-' is captured so "-3 times" isn't silently read as 3; negatives clamp to 0.
+    let signed_count = |c: regex::Captures| -> Option<usize> {
+        let n = c.get(2)?.as_str().parse::<usize>().ok()?;
+        if c.get(1).is_some() {
+            append_raw_log(
+                "LOOP_COUNT",
+                &format!("negative count -{n} clamped to 0 | {p}"),
+            );
+            return Some(0);
+        }
+        Some(n)
+    };
+
     // 1) Numerot: "7 times" / "1 time"
-    if let Some(c) = Regex::new(r"(?i)\b(\d+)\s*(?:times?|time)\b")
+    if let Some(c) = Regex::new(r"(?i)(-)?\b(\d+)\s*(?:times?|time)\b")
         .unwrap()
         .captures(p.as_str())
     {
-        return c.get(1).and_then(|m| m.as_str().parse::<usize>().ok());
+        return signed_count(c);
     }
 
     // 2) "4x" / "4 x"
-    if let Some(c) = Regex::new(r"(?i)\b(\d+)\s*x\b")
+    if let Some(c) = Regex::new(r"(?i)(-)?\b(\d+)\s*x\b")
         .unwrap()
         .captures(p.as_str())
     {
-        return c.get(1).and_then(|m| m.as_str().parse::<usize>().ok());
+        return signed_count(c);
     }
 
     // 3) once/twice/thrice
@@ -1516,6 +2374,9 @@ neuro {tmp}"#
 fn build_roleflag_dsl(prompt: &str) -> String {
     let prompt = strip_wrapping_quotes(prompt);
     let lower = prompt.to_ascii_lowercase();
+    if let Some(dsl) = build_roleflag_revoke_dsl(prompt.as_str()) {
+        return dsl;
+    }
     let var = if lower.contains("role") {
         "role"
     } else {
@@ -1538,6 +2399,47 @@ fn build_roleflag_dsl(prompt: &str) -> String {
     lines.join("\n")
 }
 
+/// Negative direction ("demote user to member", "revoke admin"): a demotion target becomes the
+/// new role, a role without a target drops to `"user"`, and anything else clears the flag.
+fn build_roleflag_revoke_dsl(prompt: &str) -> Option<String> {
+    let re_neg = Regex::new(
+        r"(?i)\b(revoke[ds]?|demote[ds]?|remove[ds]?|strip|unset|disable[ds]?|take away)\b",
+    )
+    .unwrap();
+    let verb = re_neg
+        .captures(prompt)?
+        .get(1)?
+        .as_str()
+        .to_ascii_lowercase();
+    let lower = prompt.to_ascii_lowercase();
+
+    let target = first_quoted(prompt).or_else(|| {
+        Regex::new(r"(?i)\b(?:to|as)\s+(?:an?\s+)?([A-Za-z_][\w]*)")
+            .unwrap()
+            .captures(prompt)
+            .and_then(|c| c.get(1).map(|m| m.as_str().to_string()))
+    });
+
+    let line = if let Some(target) = target.filter(|_| verb.starts_with("demote")) {
+        format!("set role = \"{}\"", sanitize_text(&target))
+    } else if verb.starts_with("demote") || lower.contains("role") {
+        "set role = \"user\"".to_string()
+    } else {
+        "set flag = false".to_string()
+    };
+    let var = if line.starts_with("set role") {
+        "role"
+    } else {
+        "flag"
+    };
+
+    let mut lines = vec![line];
+    if mentions_print(prompt) {
+        lines.push(format!("neuro {var}"));
+    }
+    Some(lines.join("\n"))
+}
+
 fn build_ai_bridge_dsl(prompt: &str) -> String {
     neuro_line(prompt)
 }
@@ -1689,7 +2591,21 @@ fn normalize_expr(expr: &str) -> String {
     parse_rhs(&e)
 }
 
+/// Macro target names come straight from the prompt; a keyword (`store 5 in if`) would make the
+/// generated `set if = 5` unparseable, so it gets a `_` prefix instead.
+fn safe_var(name: &str) -> String {
+    if crate::lexer::is_keyword(name) {
+        format!("_{name}")
+    } else {
+        name.to_string()
+    }
+}
+
 fn parse_var_expr(prompt: &str) -> Option<(String, String, bool)> {
+    parse_var_expr_raw(prompt).map(|(var, expr, do_print)| (safe_var(&var), expr, do_print))
+}
+
+fn parse_var_expr_raw(prompt: &str) -> Option<(String, String, bool)> {
     let p = prompt.trim();
     let lp = p.to_ascii_lowercase();
 
@@ -1966,11 +2882,52 @@ fn eq_case(a: &str, b: &str) -> bool {
 }
 #[inline]
 #[allow(dead_code)]
-fn var(map: &HashMap<String, String>, k: &str) -> String {
+fn var(map: &Variables, k: &str) -> String {
     map.get(k).cloned().unwrap_or_else(|| k.to_string())
 }
+/// Replaces `{ident}` with the variable's value; undefined names stay as written.
+/// `{{` / `}}` are escaped braces.
+fn interpolate(msg: &str, vars: &Variables) -> String {
+    if !msg.contains(['{', '}']) {
+        return msg.to_string();
+    }
+    let mut out = String::with_capacity(msg.len());
+    let mut rest = msg;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        if let Some(end) = tail.find('}').filter(|_| tail.starts_with('{')) {
+            let name = &tail[1..end];
+            let is_ident = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if let Some(val) = vars.get(name).filter(|_| is_ident) {
+                out.push_str(val);
+                rest = &tail[end + 1..];
+                continue;
+            }
+        }
+        out.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A condition operand: the variable's value, or the text itself. `-name` (from `if -x > 0`)
+/// is the variable's value negated; a non-numeric value just gets the `-` in front.
 #[inline]
-fn var_or_literal(map: &HashMap<String, String>, k: &str) -> String {
+fn var_or_literal(map: &Variables, k: &str) -> String {
+    if let Some(v) = k.strip_prefix('-').and_then(|name| map.get(name)) {
+        return match v.trim().parse::<f64>() {
+            Ok(n) => format!("{}", -n + 0.0), // `+ 0.0` turns `-0` into `0`.
+            Err(_) => format!("-{v}"),
+        };
+    }
     map.get(k).cloned().unwrap_or_else(|| k.to_string())
 }
 #[allow(dead_code)]
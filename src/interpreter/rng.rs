@@ -0,0 +1,108 @@
+//! Deterministic pseudo-random numbers for the random built-ins (`random(a, b)`, ...).
+//!
+//! Every random built-in draws from the one `Rng` owned by the `Interpreter`, so setting
+//! `NC_SEED` makes a whole script reproducible. Without it the seed comes from the clock.
+//! The generator is xorshift64* — fast and good enough for scripting, not for cryptography.
+
+use std::cell::Cell;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct Rng {
+    // `Cell` so expression evaluation (which only has `&Interpreter`) can advance it.
+    state: Cell<u64>,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            state: Cell::new(splitmix64(seed).max(1)),
+        }
+    }
+
+    /// Seeds from `NC_SEED` if it parses as an integer, otherwise from the clock.
+    pub fn from_env() -> Self {
+        let seed = env::var("NC_SEED")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or_else(entropy_seed);
+        Self::seeded(seed)
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        let mut x = self.state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state.set(x);
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform integer in `[lo, hi]` (inclusive). Callers guarantee `lo <= hi`.
+    pub fn range_inclusive(&self, lo: i64, hi: i64) -> i64 {
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        // Widening multiply keeps the modulo bias negligible for any span.
+        let pick = (self.next_u64() as u128 * span) >> 64;
+        (lo as i128 + pick as i128) as i64
+    }
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn entropy_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    nanos ^ (std::process::id() as u64).rotate_left(32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let a = Rng::seeded(42);
+        let b = Rng::seeded(42);
+        let xs: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..16).map(|_| b.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(
+            xs,
+            (0..16)
+                .map(|_| Rng::seeded(43).next_u64())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn zero_seed_still_produces_values() {
+        let rng = Rng::seeded(0);
+        assert!((0..4).map(|_| rng.next_u64()).any(|v| v != 0));
+    }
+
+    #[test]
+    fn range_is_inclusive_and_roughly_uniform() {
+        let rng = Rng::seeded(7);
+        let mut buckets = [0usize; 6];
+        for _ in 0..60_000 {
+            let v = rng.range_inclusive(1, 6);
+            assert!((1..=6).contains(&v));
+            buckets[(v - 1) as usize] += 1;
+        }
+        // Expect ~10_000 per face; allow a generous 10% band.
+        assert!(
+            buckets.iter().all(|&n| (9_000..=11_000).contains(&n)),
+            "{buckets:?}"
+        );
+        assert_eq!(rng.range_inclusive(5, 5), 5);
+        // The full i64 range must not overflow.
+        rng.range_inclusive(i64::MIN, i64::MAX);
+    }
+}
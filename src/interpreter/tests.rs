@@ -1,7 +1,17 @@
 //! Unit tests for the NeuroChain interpreter.
 
-use super::{extract_dsl, sanitize_lines, Interpreter};
-use crate::parser::{ASTNode, BinaryOperator, Expr};
+use super::{
+    becomes_active_model, build_macro_dsl, extract_dsl, inline_model_path, override_macro_label,
+    sanitize_lines, split_sequence, Budget, FixedClock, Interpreter, Value, MAX_LOOP_ITERATIONS,
+};
+use crate::lexer::tokenize;
+use crate::parser::{parse, ASTNode, BinaryOperator, Expr};
+use std::time::Duration;
+
+fn run_src(interp: &mut Interpreter, src: &str) -> Result<String, String> {
+    interp.run(parse(tokenize(src)?))?;
+    Ok(interp.take_output())
+}
 
 #[test]
 fn test_interpreter_set_and_add() {
@@ -16,14 +26,16 @@ fn test_interpreter_set_and_add() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(interp.variables.get("result"), Some(&"5".to_string()));
 }
 
 #[test]
 fn test_interpreter_variable_use_in_expr() {
     let mut interp = Interpreter::new();
-    interp.run(vec![ASTNode::SetVar("a".into(), Expr::Value("10".into()))]);
+    interp
+        .run(vec![ASTNode::SetVar("a".into(), Expr::Value("10".into()))])
+        .unwrap();
 
     let ast = vec![ASTNode::SetVar(
         "sum".into(),
@@ -34,7 +46,7 @@ fn test_interpreter_variable_use_in_expr() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(interp.variables.get("sum"), Some(&"15".to_string()));
 }
 
@@ -50,7 +62,7 @@ fn test_interpreter_comparison_expr() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(interp.variables.get("cmp"), Some(&"true".to_string()));
 }
 
@@ -66,7 +78,7 @@ fn test_interpreter_string_concat() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(
         interp.variables.get("combined"),
         Some(&"HelloWorld".to_string())
@@ -76,10 +88,12 @@ fn test_interpreter_string_concat() {
 #[test]
 fn test_interpreter_string_concat_with_variable() {
     let mut interp = Interpreter::new();
-    interp.run(vec![ASTNode::SetVar(
-        "name".into(),
-        Expr::StringLit("Joe".into()),
-    )]);
+    interp
+        .run(vec![ASTNode::SetVar(
+            "name".into(),
+            Expr::StringLit("Joe".into()),
+        )])
+        .unwrap();
 
     let ast = vec![ASTNode::SetVar(
         "greeting".into(),
@@ -90,7 +104,7 @@ fn test_interpreter_string_concat_with_variable() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(
         interp.variables.get("greeting"),
         Some(&"Hello,Joe".to_string())
@@ -109,7 +123,7 @@ fn test_interpreter_divide_by_zero() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(interp.variables.get("error"), Some(&"NaN".to_string()));
 }
 
@@ -125,7 +139,7 @@ fn test_interpreter_hello_universe_slogan() {
         ),
     )];
 
-    interp.run(ast);
+    interp.run(ast).unwrap();
     assert_eq!(
         interp.variables.get("slogan"),
         Some(&"HelloUniverse".to_string())
@@ -137,3 +151,1535 @@ fn strip_and_sanitize() {
     let txt = "### Instruction:\nX\n### Response:\nmacro from AI: junk\n✅ neuro \"hi\"\nfoo";
     assert_eq!(sanitize_lines(&extract_dsl(txt)), "neuro \"hi\"");
 }
+
+#[test]
+fn while_loop_runs_until_condition_is_false() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set i = 0
+while i < 3:
+    neuro i
+    set i = i + 1
+neuro "done"
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "0\n1\n2\ndone");
+    assert_eq!(interp.variables.get("i"), Some(&"3".to_string()));
+}
+
+#[test]
+fn while_loop_with_false_condition_skips_body() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+while 1 > 2:
+    neuro "never"
+neuro "after"
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "after");
+}
+
+#[test]
+fn runaway_while_loop_is_aborted() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set n = 0
+while 1 == 1:
+    set n = n + 1
+neuro "unreachable"
+"#;
+    let err = run_src(&mut interp, src).unwrap_err();
+    assert!(
+        err.contains("while loop exceeded"),
+        "unexpected error: {err}"
+    );
+    assert_eq!(
+        interp.variables.get("n"),
+        Some(&MAX_LOOP_ITERATIONS.to_string())
+    );
+    assert!(interp.take_output().is_empty());
+}
+
+#[test]
+fn output_order_follows_execution_across_nested_blocks() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+neuro "start"
+set i = 0
+while i < 3:
+    set msg = "loop " + i
+    neuro msg
+    if i == 0:
+        neuro "first"
+    elif i == 1:
+        neuro "second"
+        set j = 0
+        while j < 2:
+            set msg = "inner " + j
+            neuro msg
+            set j = j + 1
+        neuro "second done"
+    else:
+        if i == 2:
+            neuro "third"
+    set i = i + 1
+    neuro "tick"
+neuro "end"
+"#;
+    let out = run_src(&mut interp, src).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "start",
+            "loop 0",
+            "first",
+            "tick",
+            "loop 1",
+            "second",
+            "inner 0",
+            "inner 1",
+            "second done",
+            "tick",
+            "loop 2",
+            "third",
+            "tick",
+            "end",
+        ]
+    );
+}
+
+#[test]
+fn for_range_is_half_open_and_restores_loop_variable() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set i = "outer"
+set total = 0
+for i in range(1, 4):
+    set total = total + i
+    neuro i
+neuro i
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "1\n2\n3\nouter");
+    assert_eq!(interp.variables.get("total"), Some(&"6".to_string()));
+}
+
+#[test]
+fn for_range_with_empty_range_skips_body_and_drops_variable() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set n = 5
+for k in range(n, 2):
+    neuro "never"
+neuro "after"
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "after");
+    assert!(!interp.variables.contains_key("k"));
+}
+
+#[test]
+fn for_range_rejects_non_integer_bounds() {
+    let mut interp = Interpreter::new();
+    let err = run_src(&mut interp, "for i in range(0, \"x\"):\n    neuro i\n").unwrap_err();
+    assert!(err.contains("must be integers"), "unexpected error: {err}");
+}
+
+#[test]
+fn math_builtins_abs_diff_and_avg() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set a = 3
+set d = abs_diff(a, 10)
+set m = avg(2, 4, 6)
+neuro d
+neuro m
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "7\n4");
+}
+
+#[test]
+fn unknown_function_and_bad_arguments_are_reported() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set a = nope(1)
+set b = abs_diff(1)
+set c = avg("x", 2)
+"#;
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(
+        interp.variables.get("a"),
+        Some(&"❌ Unknown function: nope".to_string())
+    );
+    assert!(interp.variables["b"].contains("takes 2 arguments"));
+    assert!(interp.variables["c"].contains("expects numbers"));
+}
+
+#[test]
+fn not_negates_condition_and_binds_tighter_than_and() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set mood = "Negative"
+if not mood == "Positive":
+    neuro "meh"
+set mood = "Positive"
+if not mood == "Positive":
+    neuro "wrong"
+if not mood == "Negative" and mood == "Positive":
+    neuro "both"
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "meh\nboth");
+}
+
+#[test]
+fn grouped_condition_is_evaluated_as_written() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set a = "x"
+set b = "n"
+set c = "n"
+if (a == "x" or b == "y") and c == "z":
+    neuro "grouped"
+if a == "x" or (b == "y" and c == "z"):
+    neuro "right-grouped"
+"#;
+    assert_eq!(run_src(&mut interp, src).unwrap(), "right-grouped");
+}
+
+#[test]
+fn neuro_evaluates_full_expressions() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set total = 7
+set a = 2
+set b = 3
+neuro "score: " + total
+neuro a + b
+neuro (a + b) * 2
+neuro not_defined_yet
+neuro "  kept as written  "
+"#;
+    assert_eq!(
+        run_src(&mut interp, src).unwrap(),
+        "score: 7\n5\n10\nnot_defined_yet\n  kept as written  "
+    );
+}
+
+#[test]
+fn text_builtins_count_words_and_chars() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set words = word_count("  the quick   brown fox ")
+set none = word_count("")
+set empty = char_count("")
+set chars = char_count("häyrylä ✓")
+set title = to_upper_first("älä")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("words"), "4");
+    assert_eq!(get("none"), "0");
+    assert_eq!(get("empty"), "0");
+    assert_eq!(get("chars"), "9");
+    assert_eq!(get("title"), "Älä");
+}
+
+#[test]
+fn floor_division_rounds_toward_negative_infinity() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set a = 7 div 2
+set b = -7 div 2
+set c = 7 div -2
+set d = 9 div 0
+set e = 1 + 10 div 3
+// a real comment still works
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "3");
+    assert_eq!(get("b"), "-4");
+    assert_eq!(get("c"), "-4");
+    assert_eq!(get("d"), "NaN");
+    assert_eq!(get("e"), "4");
+}
+
+#[test]
+fn power_operator_precedence_and_edge_cases() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set r = 2 ** 10
+set chain = 2 ** 3 ** 2
+set mixed = 3 * 2 ** 2
+set neg = -2 ** 2
+set half = 2 ** -1
+set zero = 0 ** -1
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("r"), "1024");
+    assert_eq!(get("chain"), "512");
+    assert_eq!(get("mixed"), "12");
+    assert_eq!(get("neg"), "-4");
+    assert_eq!(get("half"), "0.5");
+    assert_eq!(get("zero"), "NaN");
+}
+
+#[test]
+fn bitwise_operators_work_on_integers_only() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set m = 6 & 3
+set s = 1 << 4
+set o = 5 | 2
+set x = 6 ^ 3
+set r = -16 >> 2
+set mixed = 1 | 2 + 4
+set big = 1 << 64
+set neg = 1 << -1
+set frac = 1.5 & 1
+set text = "a" | 1
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("m"), "2");
+    assert_eq!(get("s"), "16");
+    assert_eq!(get("o"), "7");
+    assert_eq!(get("x"), "5");
+    assert_eq!(get("r"), "-4");
+    assert_eq!(get("mixed"), "7");
+    assert_eq!(
+        get("big"),
+        "❌ Shift amount must be between 0 and 63, got 64"
+    );
+    assert_eq!(
+        get("neg"),
+        "❌ Shift amount must be between 0 and 63, got -1"
+    );
+    assert_eq!(
+        get("frac"),
+        "❌ Bitwise operators need integers, got '1.5' and '1'"
+    );
+    assert!(get("text").starts_with("❌ Bitwise operators need integers"));
+}
+
+#[test]
+fn string_builtins_transform_text() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set name = "Ada"
+set greeting = "  hello world  "
+set u = upper(name)
+set l = lower(name)
+set t = trim(greeting)
+set n = len("häy")
+set s = substr("NeuroChain", 0, 5)
+set tail = substr("NeuroChain", 5)
+set clipped = substr("abc", 2, 10)
+set r = replace("a-b-c", "-", "+")
+set oops = shout(name)
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("u"), "ADA");
+    assert_eq!(get("l"), "ada");
+    assert_eq!(get("t"), "hello world");
+    assert_eq!(get("n"), "3");
+    assert_eq!(get("s"), "Neuro");
+    assert_eq!(get("tail"), "Chain");
+    assert_eq!(get("clipped"), "c");
+    assert_eq!(get("r"), "a+b+c");
+    assert_eq!(get("oops"), "❌ Unknown function: shout");
+}
+
+#[test]
+fn trim_prefix_and_suffix_remove_one_occurrence() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set path = "models/sst2/model.onnx"
+set a = trim_prefix(path, "models/")
+set b = trim_prefix(path, "nope/")
+set c = trim_prefix(path, "")
+set d = trim_suffix("label: yes!!", "!")
+set e = trim_suffix("abc", "")
+set f = trim_prefix("xxa", "x")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "sst2/model.onnx");
+    assert_eq!(get("b"), "models/sst2/model.onnx");
+    assert_eq!(get("c"), "models/sst2/model.onnx");
+    assert_eq!(get("d"), "label: yes!");
+    assert_eq!(get("e"), "abc");
+    assert_eq!(get("f"), "xa");
+}
+
+#[test]
+fn string_predicates_in_conditions() {
+    let src = r#"
+set msg = "ERROR: disk full"
+set prefix = "error"
+if contains(msg, "disk") and startswith(msg, prefix):
+    neuro "disk error"
+if endswith(msg, "empty") or endswith(msg, "FULL"):
+    neuro "full"
+if not contains(msg, "warning"):
+    neuro "no warning"
+if contains(msg, "net") or (startswith(msg, "x") and contains(msg, "disk")):
+    neuro "wrong"
+set hit = contains(msg, "Disk")
+"#;
+    let mut interp = Interpreter::new();
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(out, "disk error\nfull\nno warning");
+    assert_eq!(interp.variables["hit"], "true");
+
+    // A wrong arity stops the script instead of quietly testing false.
+    let err = run_src(
+        &mut interp,
+        "if contains(msg):\n    neuro \"wrong arity\"\n",
+    )
+    .unwrap_err();
+    assert_eq!(err, "❌ contains() takes 2 arguments, got 1");
+}
+
+#[test]
+fn conditions_accept_negative_literals_and_negated_variables() {
+    let src = r#"
+set temp = -3
+set x = -4
+set zero = 0
+set a = 2
+set b = -2
+if temp < -2.5:
+    neuro "cold"
+if temp < - 2.5:
+    neuro "cold spaced"
+if -x > 0:
+    neuro "x is negative"
+if -temp >= 3:
+    neuro "three below"
+if a == -b:
+    neuro "opposite"
+if -zero == 0:
+    neuro "no negative zero"
+if -a > 0:
+    neuro "wrong"
+"#;
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    assert_eq!(
+        out,
+        "cold\ncold spaced\nx is negative\nthree below\nopposite\nno negative zero"
+    );
+}
+
+#[test]
+fn negative_and_zero_loop_counts_print_nothing() {
+    assert_eq!(super::loop_count_from_prompt("say Ping -3 times"), Some(0));
+    assert_eq!(super::loop_count_from_prompt("say Ping -2x"), Some(0));
+    assert_eq!(super::loop_count_from_prompt("say Ping 3 times"), Some(3));
+    assert_eq!(super::build_loop_dsl("say Ping -3 times"), "");
+    assert_eq!(super::build_loop_dsl("say Ping 0 times"), "");
+
+    let mut interp = Interpreter::new();
+    let src = "macro from AI: \"say Ping -3 times\"\nmacro from AI: \"say Ping 0 times\"\nneuro \"end\"\n";
+    assert_eq!(run_src(&mut interp, src).unwrap(), "end");
+}
+
+#[test]
+fn pad_number_zero_pads_without_truncating() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set a = pad_number(7, 3)
+set b = pad_number(1234, 2)
+set c = pad_number(-7, 4)
+set d = pad_number("abc", 3)
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "007");
+    assert_eq!(get("b"), "1234");
+    assert_eq!(get("c"), "-007");
+    assert!(get("d").starts_with("❌ pad_number() expects an integer"));
+}
+
+#[test]
+fn numeric_math_builtins() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set a = abs(-4.5)
+set lo = min(3, -1, 2)
+set hi = max(3, 10)
+set r = round(2.5)
+set r2 = round(-2.4)
+set r0 = round(-0.4)
+set s = sqrt(16)
+set bad = sqrt(-1)
+set first = min("banana", "Apple")
+set last = max("banana", "Apple")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "4.5");
+    assert_eq!(get("lo"), "-1");
+    assert_eq!(get("hi"), "10");
+    assert_eq!(get("r"), "3");
+    assert_eq!(get("r2"), "-2");
+    assert_eq!(get("r0"), "0");
+    assert_eq!(get("s"), "4");
+    assert_eq!(get("bad"), "NaN");
+    assert_eq!(get("first"), "Apple");
+    assert_eq!(get("last"), "banana");
+}
+
+#[test]
+fn round_and_format_fix_the_decimal_places() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set pi = round(3.14159, 2)
+set third = round(10.0 / 3.0, 3)
+set pad = round(2, 2)
+set wide = round(1, 50)
+set neg = round(3.7, -1)
+set f = format(10.0 / 3.0, "0.00")
+set f0 = format(7.6, "0")
+set z = round(-0.004, 2)
+set fz = format(-0.2, "0")
+set bad = round("x", 2)
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("pi"), "3.14");
+    assert_eq!(get("third"), "3.333");
+    assert_eq!(get("pad"), "2.00");
+    assert_eq!(get("wide"), "1.0000000000");
+    assert_eq!(get("neg"), "4");
+    assert_eq!(get("f"), "3.33");
+    assert_eq!(get("f0"), "8");
+    assert_eq!(get("z"), "0.00");
+    assert_eq!(get("fz"), "0");
+    assert!(get("bad").starts_with("❌ round() expects a number"));
+}
+
+#[test]
+fn num_forces_arithmetic_inside_a_text_chain() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set plain = "n" + 5 + 2
+set forced = "n" + num(5 + 2)
+set score = "Score: " + 5
+set points = 5 + " points"
+set trimmed = num("007") + 1
+set bad = num("abc")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("plain"), "n52");
+    assert_eq!(get("forced"), "n7");
+    assert_eq!(get("score"), "Score: 5");
+    assert_eq!(get("points"), "5 points");
+    assert_eq!(get("trimmed"), "8");
+    assert!(
+        get("bad").starts_with("❌ num() expects numbers"),
+        "{}",
+        get("bad")
+    );
+}
+
+#[test]
+fn mixed_add_chains_evaluate_left_to_right() {
+    // `+` is left-associative: numbers add until the first string appears, and from then on
+    // every operand is appended as text.
+    let mut interp = Interpreter::new();
+    let src = r#"
+set n = 5
+set a = 1 + 2 + "x"
+set b = "x" + 1 + 2
+set c = "x" + (1 + 2)
+set d = "" + n + "text"
+set e = n + n + " items"
+set f = "total: " + n + n
+set g = "4" + "2" + "!"
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "3x");
+    assert_eq!(get("b"), "x12");
+    assert_eq!(get("c"), "x3");
+    assert_eq!(get("d"), "5text");
+    assert_eq!(get("e"), "10 items");
+    assert_eq!(get("f"), "total: 55");
+    assert_eq!(get("g"), "6!");
+}
+
+#[test]
+fn pick_chooses_by_flag_truthiness() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set on = "true"
+set off = "FALSE"
+set empty = ""
+set label = "ready"
+set a = pick(on, "yes", "no")
+set b = pick(1, "yes", "no")
+set c = pick(label, "yes", "no")
+set d = pick(off, "yes", "no")
+set e = pick(empty, "yes", "no")
+set f = pick(0, "yes", "no")
+set g = pick(None, "yes", "no")
+set h = pick(3 > 2, "bigger", "smaller")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "yes");
+    assert_eq!(get("b"), "yes");
+    assert_eq!(get("c"), "yes");
+    assert_eq!(get("d"), "no");
+    assert_eq!(get("e"), "no");
+    assert_eq!(get("f"), "no");
+    assert_eq!(get("g"), "no");
+    assert_eq!(get("h"), "bigger");
+}
+
+#[test]
+fn neuro_raw_builds_one_line_until_the_next_neuro() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set n = 3
+neuro_raw "a"
+neuro_raw "b"
+neuro ""
+neuro_raw "count: "
+neuro_raw n
+neuro " items"
+neuro "next"
+neuro_raw "open"
+"#;
+    // The last, still open line is ended by `take_output`.
+    assert_eq!(
+        run_src(&mut interp, src).unwrap(),
+        "ab\ncount: 3 items\nnext\nopen"
+    );
+    assert_eq!(interp.take_output(), "");
+
+    // `clear_output` drops an open line.
+    run_src(&mut interp, "neuro_raw \"x\"\n").unwrap();
+    interp
+        .run(parse(tokenize("neuro_raw \"dropped\"\n").unwrap()))
+        .unwrap();
+    interp.clear_output();
+    assert_eq!(run_src(&mut interp, "neuro \"fresh\"\n").unwrap(), "fresh");
+}
+
+#[test]
+fn json_get_reads_dotted_paths() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set doc = """{"user": {"name": "Ada", "age": 36, "tags": ["x", "y"], "admin": true, "nick": null}}"""
+set name = json_get(doc, "user.name")
+set age = json_get(doc, "user.age")
+set tag = json_get(doc, "user.tags.1")
+set tags = json_get(doc, "user.tags")
+set admin = json_get(doc, "user.admin")
+set nick = json_get(doc, "user.nick")
+set missing = json_get(doc, "user.email")
+set too_deep = json_get(doc, "user.name.first")
+set broken = json_get("{not json", "user.name")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("name"), "Ada");
+    assert_eq!(get("age"), "36");
+    assert_eq!(get("tag"), "y");
+    assert_eq!(get("tags"), r#"["x","y"]"#);
+    assert_eq!(get("admin"), "true");
+    assert_eq!(get("nick"), "");
+    assert_eq!(get("missing"), "");
+    assert_eq!(get("too_deep"), "");
+    assert_eq!(get("broken"), "");
+}
+
+#[test]
+fn set_from_match_picks_the_matching_arm_or_default() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set cmd = "b"
+set a = match cmd: case "A": "alpha" case "B": "beta" default: "other"
+set b = match "zzz": case "A": "alpha" default: "other"
+set c = match 2 + 1: case 3: 10 * 2 case 4: 0
+set d = match cmd: case "x": "never"
+if cmd == "b":
+    set e = match cmd:
+        case "a": "alpha"
+        case "b": ["beta", "bravo"]
+        default: "other"
+    neuro "in block"
+"#;
+    let out = run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("a"), "beta");
+    assert_eq!(get("b"), "other");
+    assert_eq!(get("c"), "20");
+    assert_eq!(get("d"), "");
+    assert_eq!(get("e"), "[beta, bravo]");
+    assert_eq!(out, "in block");
+}
+
+#[test]
+fn random_is_reproducible_with_a_fixed_seed() {
+    let src = r#"
+set a = random(1, 100)
+set b = random(1, 100)
+set c = random(10, 10)
+set d = random(1, "x")
+"#;
+    let run_seeded = || {
+        let mut interp = Interpreter::new();
+        interp.rng = super::rng::Rng::seeded(1234);
+        run_src(&mut interp, src).unwrap();
+        interp.variables
+    };
+    let first = run_seeded();
+    let second = run_seeded();
+    assert_eq!(first["a"], second["a"]);
+    assert_eq!(first["b"], second["b"]);
+    let a: i64 = first["a"].parse().unwrap();
+    assert!((1..=100).contains(&a));
+    assert_eq!(first["c"], "10");
+    assert!(first["d"].starts_with("❌ random() expects integers"));
+}
+
+#[test]
+fn continue_on_error_runs_the_statements_after_a_failing_one() {
+    let src =
+        "neuro \"one\"\nneuro 1 2\nneuro \"three\"\nwhile true:\n    set z = 1\nneuro \"five\"\n";
+
+    let mut interp = Interpreter::new();
+    // By default the parse error stops the block before anything runs.
+    assert!(interp.run_source(src).is_err());
+    assert_eq!(interp.take_output(), "");
+
+    let mut interp = Interpreter::new();
+    interp.set_continue_on_error(true);
+    interp.run_source(src).unwrap();
+    // Line 2 fails to parse and the loop on line 4 aborts at runtime; both are skipped.
+    assert_eq!(interp.take_output(), "one\nthree\nfive");
+    // ...and counted, so the run as a whole still fails.
+    let err = interp.take_skipped().unwrap_err();
+    assert!(err.contains("2 statements failed"), "{err}");
+    assert!(interp.take_skipped().is_ok());
+}
+
+#[test]
+fn time_builtins_read_the_injected_clock() {
+    let mut interp = Interpreter::new();
+    interp.set_clock(FixedClock(Duration::from_millis(1_700_000_000_250)));
+    let src = "set t = now()\nset ms = now_ms()\nset bad = now(1)";
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(interp.variables["t"], "1700000000");
+    assert_eq!(interp.variables["ms"], "1700000000250");
+    assert_eq!(
+        interp.variables["bad"],
+        "❌ now() takes no arguments, got 1"
+    );
+
+    // The clock comes from the caller, so `reset` keeps it.
+    interp.reset();
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(interp.variables["t"], "1700000000");
+}
+
+#[test]
+fn overlong_macro_prompt_is_rejected_before_templates_run() {
+    let mut interp = Interpreter::new();
+    let prompt = format!("say {} 3 times", "Ping ".repeat(500));
+    let ast = vec![
+        ASTNode::MacroCall(prompt),
+        ASTNode::Neuro(Expr::StringLit("unreachable".into())),
+    ];
+    let err = interp.run(ast).unwrap_err();
+    assert!(err.contains("limit is 2000"), "unexpected error: {err}");
+    assert!(interp.take_output().is_empty());
+    // The macro model is only loaded on the heavy path.
+    assert!(interp.macro_model.is_none());
+}
+
+#[test]
+fn set_from_ai_falls_back_to_literal_when_every_model_is_missing() {
+    let mut interp = Interpreter::new();
+    let src = "set x from AI: \"hello there\" else from AI: \"missing/one.onnx\" else from AI: \"missing/two.onnx\"\nneuro x\n";
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(out.trim(), "\"hello there\"");
+    assert_eq!(interp.variables.get("x").unwrap(), "\"hello there\"");
+}
+
+#[test]
+fn set_from_ai_stores_the_else_default_when_no_model_answers() {
+    let mut interp = Interpreter::new();
+    let src = "set mood from AI: \"hello there\" else from AI: \"missing/one.onnx\" else \"Neutral\"\nset tone from AI \"missing/x.onnx\": \"hi\" else \"Unknown\"\n";
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(interp.variables.get("mood").unwrap(), "Neutral");
+    assert_eq!(interp.variables.get("tone").unwrap(), "Unknown");
+}
+
+#[test]
+fn set_boolean_round_trips_into_bare_if() {
+    let src = "set ok = 1 < 2\nif ok:\n    neuro \"entered\"\nelse:\n    neuro \"skipped\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    assert_eq!(out.trim(), "entered");
+
+    let src = "set ok = 3 < 2\nif ok:\n    neuro \"entered\"\nelse:\n    neuro \"skipped\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    assert_eq!(out.trim(), "skipped");
+
+    let src = "set a = \"1\"\nset ok = a == \"1\" and 2 == 2\nif ok and not missing:\n    neuro \"both\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    assert_eq!(out.trim(), "both");
+}
+
+#[test]
+fn comparison_results_are_canonical_booleans() {
+    let src = "set a = 5\nset b = 3\nset flag = a > b\nset off = a < b\nneuro flag\nneuro off\n\
+               if flag == \"true\":\n    neuro \"quoted\"\n\
+               if flag == true:\n    neuro \"bare\"\n\
+               if off == false and not off:\n    neuro \"off\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    let lines: Vec<&str> = out.lines().map(str::trim).collect();
+    assert_eq!(lines, vec!["true", "false", "quoted", "bare", "off"]);
+}
+
+#[test]
+fn neuro_interpolates_defined_variables() {
+    let src = "set name = \"Ada\"\nset score = 3 + 4\n\
+               neuro \"Hello {name}, your score is {score}\"\n\
+               neuro \"Missing {nobody} stays\"\n\
+               neuro \"Escaped {{name}} and }} and { name }\"\n\
+               neuro \"no braces here\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "Hello Ada, your score is 7",
+            "Missing {nobody} stays",
+            "Escaped {name} and } and { name }",
+            "no braces here",
+        ]
+    );
+}
+
+#[test]
+fn roleflag_revoke_phrasing_sets_the_negative_value() {
+    assert_eq!(
+        super::build_roleflag_dsl("Demote user to member"),
+        "set role = \"member\""
+    );
+    assert_eq!(
+        super::build_roleflag_dsl("Revoke admin"),
+        "set flag = false"
+    );
+    assert_eq!(
+        super::build_roleflag_dsl("Remove the admin role and print it"),
+        "set role = \"user\"\nneuro role"
+    );
+    // Affirmative phrasing is unchanged.
+    assert_eq!(
+        super::build_roleflag_dsl("Set role to 'admin'"),
+        "set role = admin"
+    );
+}
+
+#[test]
+fn reset_clears_variables_and_output() {
+    let mut interp = Interpreter::new();
+    interp
+        .run(parse(tokenize("set x = \"1\"\nneuro x\n").unwrap()))
+        .unwrap();
+    interp.reset();
+    assert!(interp.variables.is_empty());
+    assert_eq!(interp.take_output(), "");
+    assert_eq!(interp.model_path(), None);
+}
+
+#[test]
+fn param_reads_defines_then_prefixed_env() {
+    std::env::set_var("NC_PARAM_PARAM_TEST_CITY", "Oulu");
+    let mut interp = Interpreter::new();
+    interp.set_param("name", "World");
+    let src =
+        "set a = param(\"name\")\nset b = param(\"param_test_city\")\nset c = param(\"nope\")\n";
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(interp.variables["a"], "World");
+    assert_eq!(interp.variables["b"], "Oulu");
+    assert_eq!(interp.variables["c"], "");
+
+    interp.reset();
+    run_src(&mut interp, "set a = param(\"name\")\n").unwrap();
+    assert_eq!(interp.variables["a"], "World");
+}
+
+#[test]
+fn neuro_prints_a_bare_number_as_written() {
+    let mut interp = Interpreter::new();
+    let out = run_src(&mut interp, "neuro 42\nneuro 007\nneuro 1.50\nneuro -3\n").unwrap();
+    assert_eq!(out, "42\n007\n1.50\n-3");
+}
+
+#[test]
+fn sanitize_strips_wrapping_quotes_and_edge_punctuation() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set quoted = sanitize("'Hello world'")
+set nested = sanitize("' Positive. '")
+set shout = sanitize("Done!")
+set ask = sanitize("  really?? ")
+set dots = sanitize("Loading...")
+set inner = sanitize("a. b! c")
+set empty = sanitize("")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(get("quoted"), "Hello world");
+    assert_eq!(get("nested"), "Positive");
+    assert_eq!(get("shout"), "Done");
+    assert_eq!(get("ask"), "really");
+    assert_eq!(get("dots"), "Loading");
+    assert_eq!(get("inner"), "a. b! c");
+    assert_eq!(get("empty"), "");
+}
+
+#[test]
+fn env_reads_only_nc_script_variables() {
+    std::env::set_var("NC_SCRIPT_ENV_TEST_REGION", "eu-north");
+    std::env::set_var("ENV_TEST_SECRET", "hunter2");
+    let mut interp = Interpreter::new();
+    let src = "set a = env(\"NC_SCRIPT_ENV_TEST_REGION\")\nset b = env(\"NC_SCRIPT_ENV_TEST_UNSET\")\nset c = env(\"ENV_TEST_SECRET\")\n";
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(interp.variables["a"], "eu-north");
+    assert_eq!(interp.variables["b"], "");
+    assert!(interp.variables["c"].starts_with("❌ env() can only read NC_SCRIPT_*"));
+    assert!(!interp.variables["c"].contains("hunter2"));
+}
+
+#[test]
+fn repeat_runs_body_n_times_with_iter() {
+    let mut interp = Interpreter::new();
+    let out = run_src(&mut interp, "repeat 3:\n    neuro \"pass \" + __iter\n").unwrap();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        ["pass 0", "pass 1", "pass 2"]
+    );
+    assert!(interp.variables.get("__iter").is_none());
+
+    let out = run_src(&mut interp, "repeat 0:\n    neuro \"never\"\n").unwrap();
+    assert_eq!(out, "");
+}
+
+#[test]
+fn repeat_is_clamped_to_the_loop_cap() {
+    let mut interp = Interpreter::new();
+    let src = format!(
+        "set n = 0\nrepeat {}:\n    set n = n + 1\n",
+        MAX_LOOP_ITERATIONS + 5
+    );
+    run_src(&mut interp, &src).unwrap();
+    assert_eq!(interp.variables["n"], MAX_LOOP_ITERATIONS.to_string());
+}
+
+#[test]
+fn macro_targets_named_like_keywords_are_renamed() {
+    let dsl = super::build_setvar_dsl("store 5 in if");
+    assert!(dsl.starts_with("set _if = 5"), "dsl: {dsl}");
+    assert!(!dsl.contains("set if "), "dsl: {dsl}");
+
+    let dsl = super::build_setvar_dsl("set while to 3");
+    assert!(dsl.starts_with("set _while = 3"), "dsl: {dsl}");
+
+    // The renamed DSL must actually run.
+    let mut interp = Interpreter::new();
+    interp
+        .run(parse(
+            tokenize(&super::build_setvar_dsl("store 5 in if")).unwrap(),
+        ))
+        .unwrap();
+    assert_eq!(interp.variables["_if"], "5");
+
+    // Ordinary names are untouched.
+    assert!(super::build_setvar_dsl("set x to 5").starts_with("set x = 5"));
+}
+
+#[test]
+fn sha256_matches_known_vectors() {
+    let mut interp = Interpreter::new();
+    let src =
+        "set e = sha256(\"\")\nset a = sha256(\"abc\")\nset word = \"abc\"\nset v = sha256(word)\n";
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(
+        interp.variables["e"],
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        interp.variables["a"],
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(interp.variables["v"], interp.variables["a"]);
+}
+
+#[test]
+fn bool_literals_work_in_set_and_conditions() {
+    let src = "set b = true\nset c = False\nneuro b\nneuro c\n\
+               if b == true:\n    neuro \"b is true\"\n\
+               if b == \"true\":\n    neuro \"string compare\"\n\
+               if c == false and not c:\n    neuro \"c is false\"\n\
+               if true:\n    neuro \"literal\"\n\
+               if false:\n    neuro \"never\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        [
+            "true",
+            "false",
+            "b is true",
+            "string compare",
+            "c is false",
+            "literal"
+        ]
+    );
+}
+
+#[test]
+fn lists_print_index_and_count() {
+    let src = "set xs = [\"a\", \"b\", \"c\"]\nneuro xs\nneuro len(xs)\nneuro xs[0]\n\
+               set i = 2\nneuro xs[i]\nset ys = xs\nneuro \"ys = {ys}\"\n\
+               set grid = [[1, 2], [3, 4]]\nneuro grid[1][0]\nneuro len(\"abc\")\n";
+    let mut interp = Interpreter::new();
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        ["[a, b, c]", "3", "a", "c", "ys = [a, b, c]", "3", "3"]
+    );
+    assert_eq!(interp.variables["ys"], "[a, b, c]");
+    assert!(matches!(interp.variables.value("grid"), Some(Value::List(rows)) if rows.len() == 2));
+}
+
+#[test]
+fn split_lists_print_readably_and_join_back() {
+    let src = "set csv = \"red,green,,blue\"\nset xs = split(csv, \",\")\nneuro xs\n\
+               neuro len(xs)\nneuro xs[1]\nneuro join(xs, \",\")\nneuro join(xs, \" | \")\n\
+               neuro \"items: {xs}\"\nset ns = [1, 2.5, \"x\"]\nneuro join(ns, \", \")\n";
+    let mut interp = Interpreter::new();
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        [
+            "[red, green, , blue]",
+            "4",
+            "green",
+            "red,green,,blue",
+            "red | green |  | blue",
+            "items: [red, green, , blue]",
+            "1, 2.5, x",
+        ]
+    );
+    // `join` undoes `split` for any separator, including around empty pieces.
+    for (s, sep) in [("a b c", " "), ("", ","), (",x,", ","), ("1::2", "::")] {
+        let src = format!("set r = join(split(\"{s}\", \"{sep}\"), \"{sep}\")\n");
+        run_src(&mut interp, &src).unwrap();
+        assert_eq!(interp.variables["r"], s, "round trip of {s:?} on {sep:?}");
+    }
+    run_src(
+        &mut interp,
+        "set e = split(\"ab\", \"\")\nset f = join(\"ab\", \",\")\n",
+    )
+    .unwrap();
+    assert!(interp.variables["e"].contains("non-empty separator"));
+    assert!(interp.variables["f"].contains("expects a list"));
+}
+
+#[test]
+fn bad_list_indexes_are_error_values() {
+    let src = "set xs = [1, 2]\nset a = xs[2]\nset b = xs[\"x\"]\nset s = \"ab\"\nset c = s[0]\n";
+    let mut interp = Interpreter::new();
+    run_src(&mut interp, src).unwrap();
+    assert!(interp.variables["a"].contains("out of range"));
+    assert!(interp.variables["b"].contains("non-negative integer"));
+    assert!(interp.variables["c"].contains("not a list"));
+}
+
+#[test]
+fn concat_list_joins_items_in_printed_form() {
+    let src = "set xs = [\"a\", 2, 1.5, [\"x\", 3]]\n\
+               set joined = concat_list(xs, \" | \")\n\
+               set tight = concat_list([1, 2, 3], \"\")\n\
+               set none = concat_list([], \",\")\n\
+               set s = \"abc\"\n\
+               set bad = concat_list(s, \",\")\n\
+               set short = concat_list(xs)\n";
+    let mut interp = Interpreter::new();
+    run_src(&mut interp, src).unwrap();
+    assert_eq!(interp.variables["joined"], "a | 2 | 1.5 | [x, 3]");
+    assert_eq!(interp.variables["tight"], "123");
+    assert_eq!(interp.variables["none"], "");
+    assert_eq!(
+        interp.variables["bad"],
+        "❌ concat_list() expects a list, got 'abc'"
+    );
+    assert_eq!(
+        interp.variables["short"],
+        "❌ concat_list() takes 2 arguments, got 1"
+    );
+}
+
+#[test]
+fn output_prefix_does_not_leak_into_take_output() {
+    std::env::set_var("NC_OUTPUT_PREFIX", ">> ");
+    let mut interp = Interpreter::new();
+    let out = run_src(&mut interp, "neuro \"hi\"\nneuro 1 + 1\n").unwrap();
+    std::env::remove_var("NC_OUTPUT_PREFIX");
+    assert_eq!(out, "hi\n2");
+}
+
+#[test]
+fn set_combines_comparisons_with_and_or() {
+    let src = "set a = 1\nset both = a == 1 and 2 == 3\nset either = a == 1 or 2 == 3\n\
+               neuro both\nneuro either\nneuro a > 0 and \"\"\n";
+    let out = run_src(&mut Interpreter::new(), src).unwrap();
+    assert_eq!(out, "false\ntrue\nfalse");
+}
+
+#[test]
+fn capitalize_each_sentence_handles_terminators() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set many = capitalize_each_sentence("the model says hi. is it sure?  yes!it is... ok")
+set one = capitalize_each_sentence("  no terminators here at all")
+set nums = capitalize_each_sentence("version 3.5 is out. (next) soon")
+"#;
+    run_src(&mut interp, src).unwrap();
+    let get = |k: &str| interp.variables[k].clone();
+    assert_eq!(
+        get("many"),
+        "The model says hi. Is it sure?  Yes!It is... Ok"
+    );
+    assert_eq!(get("one"), "No terminators here at all");
+    assert_eq!(get("nums"), "Version 3.5 is out. (Next) soon");
+}
+
+#[test]
+fn ascii_only_mode_strips_non_ascii_from_output() {
+    let mut interp = Interpreter::new();
+    interp.ascii_only = true;
+    let src =
+        "neuro \"✅ Done\"\nneuro \"⚠️ häyrylä ❌\"\nset x = 1 / 0\nneuro x\nneuro \"plain\"\n";
+    let out = run_src(&mut interp, src).unwrap();
+    assert!(out.is_ascii(), "{out:?}");
+    assert_eq!(out, "Done\nhyryl \nNaN\nplain");
+
+    // Only symbols go: spaces inside and around the text are kept.
+    let src = "neuro_raw \"Loading \"\nneuro \"done\"\nneuro \"  - item ✓\"\n";
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(out, "Loading done\n  - item ");
+}
+
+#[test]
+fn inline_model_resolves_ids_and_paths() {
+    assert!(inline_model_path("sst2").ends_with("/distilbert-sst2/model.onnx"));
+    assert!(inline_model_path("TOXIC").ends_with("/toxic_quantized/model.onnx"));
+    assert_eq!(inline_model_path("custom/m.onnx"), "custom/m.onnx");
+    assert_eq!(inline_model_path("nope"), "nope");
+
+    // No model files in a source checkout: the prompt is stored, as with a missing active model.
+    let mut interp = Interpreter::new();
+    run_src(&mut interp, "set mood from AI \"nope\": \"great\"\n").unwrap();
+    assert_eq!(interp.model_path(), None);
+    assert!(interp.variables["mood"].contains("great"));
+}
+
+#[test]
+fn model_directive_resolves_ids() {
+    let mut interp = Interpreter::new();
+    let err = run_src(&mut interp, "# @model nope\nneuro \"never\"\n").unwrap_err();
+    assert!(err.contains("Unknown model id 'nope'"), "{err}");
+
+    // Other `# @...` lines stay comments.
+    let mut interp = Interpreter::new();
+    assert_eq!(
+        run_src(&mut interp, "# @todo tidy up\nneuro \"ok\"\n").unwrap(),
+        "ok"
+    );
+
+    let sst2 = inline_model_path("sst2");
+    if !std::path::Path::new(&sst2).exists() {
+        eprintln!("skipping # @model load check; missing {sst2}");
+        return;
+    }
+    let mut interp = Interpreter::new();
+    run_src(&mut interp, "# @model SST2\n").unwrap();
+    assert_eq!(interp.model_path(), Some(sst2.as_str()));
+}
+
+#[test]
+fn set_from_ai_picks_loaded_model_by_id() {
+    let sst2 = "models/distilbert-sst2/model.onnx";
+    let toxic = "models/toxic_quantized/model.onnx";
+    if [sst2, toxic]
+        .iter()
+        .any(|p| !std::path::Path::new(p).exists())
+    {
+        eprintln!("skipping two-model test; models missing");
+        return;
+    }
+
+    let mut interp = Interpreter::new();
+    let src = format!(
+        "AI: \"{sst2}\"\nAI: \"{toxic}\"\n\
+         set mood from AI \"sst2\": \"This is wonderful!\"\n\
+         set tone from AI \"toxic\": \"You are wonderful!\"\n"
+    );
+    run_src(&mut interp, &src).unwrap();
+    // The last `AI:` stays the default, but each ID picks the model of its own kind.
+    assert_eq!(interp.model_path(), Some(toxic));
+    assert!(["Positive", "Negative"].contains(&interp.variables["mood"].as_str()));
+    assert!(["Toxic", "Not toxic"].contains(&interp.variables["tone"].as_str()));
+}
+
+#[test]
+fn macro_model_takes_the_classifier_slot_only_when_it_is_free() {
+    use crate::ai::model::ModelKind::{MacroIntent, Toxic, SST2};
+
+    // Active `set x from AI:` kind after loading each kind in turn, from no model.
+    let active_after = |loads: &[crate::ai::model::ModelKind]| {
+        loads.iter().fold(None, |active, &kind| {
+            if becomes_active_model(kind, active) {
+                Some(kind)
+            } else {
+                active
+            }
+        })
+    };
+    assert_eq!(active_after(&[SST2, MacroIntent]), Some(SST2));
+    assert_eq!(active_after(&[MacroIntent, SST2]), Some(SST2));
+    assert_eq!(active_after(&[MacroIntent]), Some(MacroIntent));
+    assert_eq!(active_after(&[MacroIntent, MacroIntent]), Some(MacroIntent));
+    // Classifiers still replace each other.
+    assert_eq!(active_after(&[SST2, MacroIntent, Toxic]), Some(Toxic));
+}
+
+#[test]
+fn classifier_and_macro_model_stay_active_together() {
+    let sst2 = "models/distilbert-sst2/model.onnx";
+    let macro_path = "models/intent_macro/model.onnx";
+    if [sst2, macro_path]
+        .iter()
+        .any(|p| !std::path::Path::new(p).exists())
+    {
+        eprintln!("skipping classifier + macro test; models missing");
+        return;
+    }
+
+    for order in [[sst2, macro_path], [macro_path, sst2]] {
+        let mut interp = Interpreter::new();
+        let src = format!(
+            "AI: \"{}\"\nAI: \"{}\"\n\
+             set mood from AI: \"This is wonderful!\"\n\
+             macro from AI: \"Show Ping 2 times\"\n",
+            order[0], order[1]
+        );
+        let out = run_src(&mut interp, &src).unwrap();
+        // `set from AI:` goes to the classifier, `macro from AI:` to the macro model.
+        assert_eq!(interp.model_path(), Some(sst2), "{order:?}");
+        assert!(
+            ["Positive", "Negative"].contains(&interp.variables["mood"].as_str()),
+            "{order:?}"
+        );
+        assert!(
+            matches!(
+                interp.macro_model.as_ref().map(|m| m.kind()),
+                Some(crate::ai::model::ModelKind::MacroIntent)
+            ),
+            "{order:?}"
+        );
+        assert_eq!(out.matches("Ping").count(), 2, "{order:?}: {out}");
+    }
+}
+
+#[test]
+fn macro_label_rules_resolve_overlaps_in_order() {
+    let cases = [
+        // Assigns and prints: `store` is not a print prefix, so the assignment rule wins.
+        (
+            "store result and print it",
+            "DocPrint",
+            Some(("assignment", "SetVar")),
+        ),
+        // Starts like a print but also assigns: no rule fires, the classifier decides.
+        ("print the total and store it in x", "SetVar", None),
+        // A print prefix beats the quoted-literal concat.
+        (
+            "say \"Hello\" joined with \"World\"",
+            "Concat",
+            Some(("print_prefix", "DocPrint")),
+        ),
+        // Concat beats the assignment rule.
+        (
+            "set msg to join \"a\" and \"b\"",
+            "SetVar",
+            Some(("quoted_concat", "Concat")),
+        ),
+        // A comment request prints, unless it also assigns.
+        (
+            "write a comment using // that says hi",
+            "SetVar",
+            Some(("comment", "DocPrint")),
+        ),
+        ("set x to 5 + 2", "SetVar", Some(("assignment", "Arith"))),
+        // A print that reads as a loop keeps its Loop label.
+        ("print \"tick\" 3 times", "Loop", None),
+        (
+            "if x > 1 then say hi",
+            "Loop",
+            Some(("loop_false_positive", "Branch")),
+        ),
+    ];
+    for (prompt, base, want) in cases {
+        assert_eq!(override_macro_label(prompt, base), want, "{prompt:?}");
+    }
+}
+
+#[test]
+fn macro_sequences_expand_every_step_in_order() {
+    let dsl = Interpreter::new()
+        .macro_to_dsl("set x to 5 and then say Done and then say Bye")
+        .unwrap();
+    let lines: Vec<&str> = dsl.lines().collect();
+    let pos = |pred: &dyn Fn(&str) -> bool| lines.iter().position(|l| pred(l)).expect(&dsl);
+    let set = pos(&|l| l == "set x = 5");
+    let done = pos(&|l| l.starts_with("neuro") && l.contains("Done"));
+    let bye = pos(&|l| l.starts_with("neuro") && l.contains("Bye"));
+    assert!(set < done && done < bye, "{dsl}");
+
+    let dsl = Interpreter::new()
+        .macro_to_dsl("store 3 in n then say Ready and then show n")
+        .unwrap();
+    assert!(dsl.trim_end().ends_with("neuro n"), "{dsl}");
+
+    let mut interp = Interpreter::new();
+    let out = run_src(
+        &mut interp,
+        "macro from AI: \"set x to 5 and then say Done and then say Bye\"\n",
+    )
+    .unwrap();
+    assert!(out.ends_with("Done\nBye"), "{out}");
+    assert_eq!(interp.variables["x"], "5");
+}
+
+#[test]
+fn split_sequence_keeps_branches_and_quotes_whole() {
+    assert_eq!(
+        split_sequence("say Hi then say 'now then' AND THEN say Bye"),
+        ["say Hi", "say 'now then'", "say Bye"]
+    );
+    assert_eq!(
+        split_sequence("set a to 1 then if a > 0 then say yes else say no"),
+        ["set a to 1", "if a > 0 then say yes else say no"]
+    );
+    assert_eq!(split_sequence("Show Ping 3 times"), ["Show Ping 3 times"]);
+}
+
+/// Builds the macro DSL for `prompt` with `label` forced (no model) and compares it with
+/// `expected`. On mismatch the panic message is a line-by-line diff: `-` expected, `+` actual.
+fn assert_dsl_eq(label: &str, prompt: &str, expected: &str) {
+    let actual = build_macro_dsl(label, prompt);
+    if actual == expected {
+        return;
+    }
+    let (exp, act): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let mut diff = String::new();
+    for i in 0..exp.len().max(act.len()) {
+        match (exp.get(i), act.get(i)) {
+            (Some(e), Some(a)) if e == a => diff.push_str(&format!("  {:>3} {e}\n", i + 1)),
+            (e, a) => {
+                if let Some(e) = e {
+                    diff.push_str(&format!("- {:>3} {e}\n", i + 1));
+                }
+                if let Some(a) = a {
+                    diff.push_str(&format!("+ {:>3} {a}\n", i + 1));
+                }
+            }
+        }
+    }
+    panic!("generated DSL differs for {label} prompt {prompt:?}:\n{diff}");
+}
+
+#[test]
+fn golden_macro_dsl_per_label() {
+    assert_dsl_eq(
+        "Loop",
+        "Show Ping 3 times",
+        "neuro \"Ping\"\nneuro \"Ping\"\nneuro \"Ping\"",
+    );
+    assert_dsl_eq("Loop", "Echo \"Hi\" 0 times", "");
+    assert_dsl_eq(
+        "Branch",
+        "If temp > 30 print Hot else print Mild",
+        "if temp > 30:\n    neuro \"Hot\"\nelse:\n    neuro \"Mild\"",
+    );
+    assert_dsl_eq(
+        "Branch",
+        "if x == 1 print one",
+        "if x == 1:\n    neuro \"one\"",
+    );
+    assert_dsl_eq(
+        "Arith",
+        "Set total to price * qty",
+        "set total = price * qty",
+    );
+    assert_dsl_eq(
+        "Arith",
+        "Calculate (a + b) * 2 and store in r",
+        "set r = (a + b) * 2",
+    );
+    assert_dsl_eq(
+        "Concat",
+        "Concatenate name and score and store in result then print it",
+        "set result = name + score\nneuro result",
+    );
+    assert_dsl_eq(
+        "Concat",
+        "Join \"Hello\" and \"World\" into greeting and print it",
+        "set greeting = \"Hello\" + \"World\"\nneuro greeting",
+    );
+}
+
+#[test]
+#[should_panic(expected = "- ")]
+fn assert_dsl_eq_reports_a_line_diff() {
+    assert_dsl_eq(
+        "Loop",
+        "Show Ping 2 times",
+        "neuro \"Ping\"\nneuro \"Pong\"",
+    );
+}
+
+#[test]
+fn budget_caps_output_bytes() {
+    let budget = Budget {
+        max_output_bytes: Some(10),
+        ..Budget::unlimited()
+    };
+    let mut interp = Interpreter::with_budget(budget);
+    let err = run_src(
+        &mut interp,
+        "neuro \"12345\"\nneuro \"67890\"\nneuro \"x\"\n",
+    )
+    .unwrap_err();
+    assert!(
+        err.contains("Budget exceeded: output is over 10 bytes"),
+        "{err}"
+    );
+    // Everything up to the limit was still printed.
+    assert_eq!(interp.take_output(), "12345\n67890");
+}
+
+#[test]
+fn budget_caps_loop_iterations_across_loops() {
+    let budget = Budget {
+        max_iterations: Some(5),
+        ..Budget::unlimited()
+    };
+    let mut interp = Interpreter::with_budget(budget);
+    let src = "for i in range(0, 3):\n    neuro i\nset n = 0\nwhile n < 10:\n    set n = n + 1\n";
+    let err = run_src(&mut interp, src).unwrap_err();
+    assert!(err.contains("more than 5 loop iterations"), "{err}");
+    assert_eq!(interp.variables.get("n").unwrap(), "2");
+
+    let mut interp = Interpreter::with_budget(budget);
+    let err = run_src(&mut interp, "repeat 6:\n    neuro \"x\"\n").unwrap_err();
+    assert!(err.contains("Budget exceeded"), "{err}");
+}
+
+#[test]
+fn deadline_stops_the_run_at_the_next_loop_iteration() {
+    let mut interp = Interpreter::new();
+    interp.set_deadline(std::time::Instant::now());
+    let src = "neuro \"before\"\nset n = 0\nwhile n < 1:\n    neuro \"spin\"\n";
+    let err = run_src(&mut interp, src).unwrap_err();
+    assert!(err.contains("Time limit reached"), "{err}");
+    assert_eq!(interp.take_output(), "before");
+
+    let err = run_src(&mut interp, "repeat 3:\n    neuro \"x\"\n").unwrap_err();
+    assert!(err.contains("Time limit reached"), "{err}");
+}
+
+#[test]
+fn budget_caps_new_variables_but_not_reassignment() {
+    let budget = Budget {
+        max_variables: Some(2),
+        ..Budget::unlimited()
+    };
+    let mut interp = Interpreter::with_budget(budget);
+    run_src(&mut interp, "set a = 1\nset b = 2\nset a = 3\n").unwrap();
+    let err = run_src(&mut interp, "set c = 4\n").unwrap_err();
+    assert!(err.contains("more than 2 variables"), "{err}");
+    let err = run_src(&mut interp, "set d from AI: \"hi\"\n").unwrap_err();
+    assert!(err.contains("more than 2 variables"), "{err}");
+    assert!(!interp.variables.contains_key("c"));
+
+    // `reset` keeps the limits but forgets what was used.
+    interp.reset();
+    run_src(&mut interp, "set x = 1\nset y = 2\n").unwrap();
+    assert!(run_src(&mut interp, "set z = 3\n").is_err());
+}
+
+#[test]
+fn missing_model_path_is_reported_and_the_script_continues() {
+    let mut interp = Interpreter::new();
+    let src = "AI: \"missing/nope/model.onnx\"\nset mood from AI: \"great\"\nneuro \"after\"\n";
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(out, "❌ Model not found: missing/nope/model.onnx\nafter");
+    assert!(interp.model_path().is_none());
+    assert_eq!(interp.variables.get("mood").unwrap(), "\"great\"");
+}
+
+#[test]
+fn ai_path_can_come_from_a_variable_or_interpolation() {
+    let mut interp = Interpreter::new();
+    let src = r#"
+set m = "missing/var/model.onnx"
+AI: m
+set dir = "other"
+AI: "missing/{dir}/model.onnx"
+AI: unset_name
+neuro "after"
+"#;
+    let out = run_src(&mut interp, src).unwrap();
+    assert_eq!(
+        out,
+        "❌ Model not found: missing/var/model.onnx\n\
+         ❌ Model not found: missing/other/model.onnx\n\
+         ❌ AI: variable 'unset_name' is not set (expected a model path)\n\
+         after"
+    );
+    assert!(interp.model_path().is_none());
+}
+
+#[test]
+fn macro_prompts_that_name_a_macro_expand_once() {
+    // Strings have no escapes and the templates never add a quote layer, so a nested
+    // `macro from AI:` can only come out as text: expansion always stops after one level.
+    let cases = [
+        (
+            "macro from AI: \"say macro from AI: say hi\"\n",
+            "macro from AI: say hi",
+        ),
+        (
+            "macro from AI: \"\"\"say 'macro from AI: say \"macro from AI: say hi\"'\"\"\"\n",
+            "macro from AI: say macro from AI: say hi",
+        ),
+        (
+            "macro from AI: \"if 1 == 1: macro from AI: say hi, say ok\"\n",
+            "hi, say ok",
+        ),
+    ];
+    for (src, want) in cases {
+        let mut interp = Interpreter::new();
+        assert_eq!(run_src(&mut interp, src).as_deref(), Ok(want), "{src:?}");
+    }
+}
@@ -0,0 +1,77 @@
+//! Typed script values.
+//!
+//! Most of the interpreter still works on text; a `Value` only matters where the type does
+//! (lists). Every value has a canonical text form (`Display`), which is what string-based code
+//! sees: `neuro`, comparisons, `{var}` interpolation and the REPL `vars` dump.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Num(f64),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Classifies script text. Only numbers whose text form round-trips become `Num`, so
+    /// `"007"` or `"1.50"` keep their exact spelling as `Str`.
+    pub fn from_text(text: String) -> Self {
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() && n.to_string() == text => Value::Num(n),
+            _ => Value::Str(text),
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Lists print as `[a, b, c]`: items unquoted, nested lists bracketed.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => f.write_str(s),
+            Value::Num(n) => write!(f, "{n}"),
+            Value::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn from_text_keeps_exact_spelling() {
+        assert_eq!(Value::from_text("42".into()), Value::Num(42.0));
+        assert_eq!(Value::from_text("-2.5".into()), Value::Num(-2.5));
+        assert_eq!(Value::from_text("007".into()), Value::Str("007".into()));
+        assert_eq!(Value::from_text("1.50".into()), Value::Str("1.50".into()));
+        assert_eq!(Value::from_text("NaN".into()), Value::Str("NaN".into()));
+    }
+
+    #[test]
+    fn lists_display_bracketed() {
+        let v = Value::List(vec![
+            Value::Str("a".into()),
+            Value::Num(2.0),
+            Value::List(vec![Value::Str("x".into())]),
+        ]);
+        assert_eq!(v.to_string(), "[a, 2, [x]]");
+        assert_eq!(Value::List(Vec::new()).to_string(), "[]");
+    }
+}
@@ -0,0 +1,160 @@
+//! Script variables, kept in assignment order.
+//!
+//! Lookups go through a name → slot index, iteration walks the slots in the order the names
+//! were first set, so dumping variables (REPL `vars`, debugging) is deterministic.
+//!
+//! Each slot holds the typed [`Value`] and its text form. The string API (`get`, `insert`,
+//! `iter`, indexing) works on the text, so code that only deals in strings never sees a list.
+
+use std::collections::HashMap;
+use std::ops::Index;
+
+use super::value::Value;
+
+#[derive(Debug, Clone)]
+struct Slot {
+    name: String,
+    text: String,
+    value: Value,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Variables {
+    entries: Vec<Slot>,
+    index: HashMap<String, usize>,
+}
+
+impl Variables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Text form of `name` (lists as `[a, b]`).
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.index.get(name).map(|&i| &self.entries[i].text)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.index.get(name).map(|&i| &self.entries[i].value)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// Sets `name` from text, returning the previous text. Re-assigning keeps the original
+    /// position.
+    pub fn insert(&mut self, name: String, text: String) -> Option<String> {
+        let value = Value::from_text(text.clone());
+        self.put(name, text, value)
+    }
+
+    /// Sets `name` to a typed value, returning the previous text.
+    pub fn insert_value(&mut self, name: String, value: Value) -> Option<String> {
+        self.put(name, value.to_string(), value)
+    }
+
+    fn put(&mut self, name: String, text: String, value: Value) -> Option<String> {
+        match self.index.get(&name) {
+            Some(&i) => {
+                let slot = &mut self.entries[i];
+                slot.value = value;
+                Some(std::mem::replace(&mut slot.text, text))
+            }
+            None => {
+                self.index.insert(name.clone(), self.entries.len());
+                self.entries.push(Slot { name, text, value });
+                None
+            }
+        }
+    }
+
+    /// Removes `name`, returning its text; the remaining variables keep their relative order.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let i = self.index.remove(name)?;
+        let slot = self.entries.remove(i);
+        for idx in self.index.values_mut() {
+            if *idx > i {
+                *idx -= 1;
+            }
+        }
+        Some(slot.text)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.entries.iter().map(|slot| (&slot.name, &slot.text))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+}
+
+impl Index<&str> for Variables {
+    type Output = String;
+
+    fn index(&self, name: &str) -> &String {
+        self.get(name)
+            .unwrap_or_else(|| panic!("variable '{name}' is not set"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterates_in_first_assignment_order() {
+        let mut vars = Variables::new();
+        vars.insert("b".into(), "1".into());
+        vars.insert("a".into(), "2".into());
+        vars.insert("c".into(), "3".into());
+        assert_eq!(vars.insert("b".into(), "4".into()), Some("1".into()));
+
+        let names: Vec<&str> = vars.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+        assert_eq!(vars["b"], "4");
+    }
+
+    #[test]
+    fn remove_keeps_lookups_consistent() {
+        let mut vars = Variables::new();
+        for (k, v) in [("x", "1"), ("y", "2"), ("z", "3")] {
+            vars.insert(k.into(), v.into());
+        }
+        assert_eq!(vars.remove("x"), Some("1".into()));
+        assert_eq!(vars.remove("x"), None);
+        assert_eq!(vars.get("z").map(String::as_str), Some("3"));
+        assert_eq!(vars.len(), 2);
+
+        vars.insert("x".into(), "5".into());
+        let pairs: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(pairs, [("y", "2"), ("z", "3"), ("x", "5")]);
+    }
+
+    #[test]
+    fn lists_keep_their_type_and_text() {
+        let mut vars = Variables::new();
+        let list = Value::List(vec![Value::Str("a".into()), Value::Num(1.0)]);
+        vars.insert_value("xs".into(), list.clone());
+        vars.insert("n".into(), "3".into());
+
+        assert_eq!(vars.value("xs"), Some(&list));
+        assert_eq!(vars["xs"], "[a, 1]");
+        assert_eq!(vars.value("n"), Some(&Value::Num(3.0)));
+        assert_eq!(
+            vars.insert("xs".into(), "007".into()),
+            Some("[a, 1]".into())
+        );
+        assert_eq!(vars.value("xs"), Some(&Value::Str("007".into())));
+    }
+}
@@ -11,46 +11,211 @@ pub const DEBUG_MODE: bool = cfg!(debug_assertions);
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     AI,
-    Neuro, // Unified output command (replaces Say/Print).
+    Neuro,    // Unified output command (replaces Say/Print).
+    NeuroRaw, // `neuro_raw`: same, without ending the line.
     Set,
     From,
     Macro, // `macro from AI: ...`
     If,
     Elif,
     Else,
+    // Keywords that are also everyday words in `macro from AI:` prompts ("repeat 3 times",
+    // "store 5 in x") keep their source spelling, so the prompt reaches the model as written.
+    While(String),
+    For(String),
+    In(String),
+    Repeat(String),
     Colon,
+    Comma,
     Equals,
     NotEquals,
     EqualsAssign,
     String(String),
     Number(String),
+    True(String),
+    False(String),
     Newline,
     Indent,
     Dedent,
     And,
     Or,
+    Not(String),
     Comment,
+    /// `# @model sst2`: a comment line starting with `@`, kept as `model sst2`.
+    Directive(String),
+    /// Source line (1-based) of the statement that follows; only from [`tokenize_with_lines`].
+    Line(usize),
 
     // Arithmetic and comparison operators.
     Plus,
     Minus,
     Star,
+    Power, // `**`
     Slash,
+    FloorDiv(String), // `div` keyword (`//` already starts a comment)
     Percent,
     GreaterThan,
     LessThan,
     GreaterEqual,
     LessEqual,
 
+    // Bitwise operators (integers only).
+    Amp,
+    Pipe,
+    Caret,
+    Shl, // `<<`
+    Shr, // `>>`
+
     LParen,
     RParen,
+    LBracket,
+    RBracket,
+}
+
+/// Keyword token for `word` (case-insensitive), or `None` for a plain identifier.
+fn keyword(word: &str) -> Option<Token> {
+    Some(match word.to_lowercase().as_str() {
+        "if" => Token::If,
+        "elif" => Token::Elif,
+        "else" => Token::Else,
+        "while" => Token::While(word.to_string()),
+        "for" => Token::For(word.to_string()),
+        "in" => Token::In(word.to_string()),
+        "repeat" => Token::Repeat(word.to_string()),
+        "neuro" => Token::Neuro,
+        "neuro_raw" => Token::NeuroRaw,
+        "set" => Token::Set,
+        "from" => Token::From,
+        "macro" => Token::Macro,
+        "ai" => Token::AI,
+        "and" => Token::And,
+        "or" => Token::Or,
+        "not" => Token::Not(word.to_string()),
+        "div" => Token::FloorDiv(word.to_string()),
+        "true" => Token::True(word.to_string()),
+        "false" => Token::False(word.to_string()),
+        _ => return None,
+    })
+}
+
+/// True if `word` can't be used as a variable name because it lexes as a keyword.
+pub fn is_keyword(word: &str) -> bool {
+    keyword(word).is_some()
+}
+
+/// Stands in for a lifted `"""..."""` string: `TRIPLE_MARK`, its index, then `;`.
+const TRIPLE_MARK: char = '\u{E000}';
+
+/// Lifts every triple-quoted string out of `input` so the per-line pass never sees a string that
+/// spans lines. Each one becomes a `TRIPLE_MARK<n>;` placeholder; the newlines it swallowed are
+/// re-added after the line it closes on, so later line numbers still match the source. A `"""`
+/// inside a `#` / `//` comment or a one-line `"..."` string is left alone.
+fn lift_triple_quoted(input: &str) -> Result<(String, Vec<String>), String> {
+    fn push_segment(out: &mut String, seg: &str, pending: &mut usize) {
+        match seg.find('\n') {
+            Some(nl) if *pending > 0 => {
+                out.push_str(&seg[..=nl]);
+                out.extend(std::iter::repeat_n('\n', std::mem::take(pending)));
+                out.push_str(&seg[nl + 1..]);
+            }
+            _ => out.push_str(seg),
+        }
+    }
+
+    let mut out = String::with_capacity(input.len());
+    let mut strings = Vec::new();
+    let mut pending = 0;
+    let mut copied = 0;
+    let (mut in_string, mut in_comment) = (false, false);
+    let mut i = 0;
+    // Same quote and comment rules as the per-line pass; all of them end with the line.
+    let bytes = input.as_bytes();
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        match bytes[i] {
+            b'\n' => (in_string, in_comment) = (false, false),
+            _ if in_comment => {}
+            b'"' if !in_string && rest.starts_with(b"\"\"\"") => {
+                push_segment(&mut out, &input[copied..i], &mut pending);
+                let body = &input[i + 3..];
+                let Some(end) = body.find("\"\"\"") else {
+                    return Err(format!(
+                        "❌ Unterminated triple-quoted string starting on line {}",
+                        input[..i].matches('\n').count() + 1
+                    ));
+                };
+                let content = &body[..end];
+                pending += content.matches('\n').count();
+                out.push(TRIPLE_MARK);
+                out.push_str(&strings.len().to_string());
+                out.push(';');
+                strings.push(content.to_string());
+                i += 3 + end + 3;
+                copied = i;
+                continue;
+            }
+            b'"' => in_string = !in_string,
+            b'#' if !in_string => in_comment = true,
+            b'/' if !in_string && rest.starts_with(b"//") => in_comment = true,
+            _ => {}
+        }
+        i += 1;
+    }
+    push_segment(&mut out, &input[copied..], &mut pending);
+    out.extend(std::iter::repeat_n('\n', pending));
+    Ok((out, strings))
+}
+
+/// Emits `Indent`/`Dedent` for the leading spaces of `raw_line` against the open blocks.
+fn push_indentation(raw_line: &str, indent_stack: &mut Vec<usize>, tokens: &mut Vec<Token>) {
+    let indent = raw_line.chars().take_while(|c| *c == ' ').count();
+    match indent.cmp(indent_stack.last().unwrap()) {
+        std::cmp::Ordering::Greater => {
+            indent_stack.push(indent);
+            tokens.push(Token::Indent);
+        }
+        std::cmp::Ordering::Less => {
+            while indent < *indent_stack.last().unwrap() {
+                indent_stack.pop();
+                tokens.push(Token::Dedent);
+            }
+        }
+        _ => {}
+    }
 }
 
 pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    lex(input, None)
+}
+
+/// [`tokenize`], plus a [`Token::Line`] in front of every statement (after its indentation).
+/// `first_line` is the number of the first line of `input` in the whole script.
+pub fn tokenize_with_lines(input: &str, first_line: usize) -> Result<Vec<Token>, String> {
+    lex(input, Some(first_line))
+}
+
+fn lex(input: &str, first_line: Option<usize>) -> Result<Vec<Token>, String> {
     let mut tokens = Vec::new();
     let mut indent_stack = vec![0];
+    let (input, triple_strings) = lift_triple_quoted(input)?;
 
     for (line_idx, raw_line) in input.lines().enumerate() {
+        // `# @name arg` is a directive, not a comment. It is a statement, so it takes part in
+        // indentation like any other line.
+        let directive = raw_line
+            .trim_start()
+            .strip_prefix('#')
+            .and_then(|rest| rest.trim_start().strip_prefix('@'));
+        if let Some(directive) = directive {
+            push_indentation(raw_line, &mut indent_stack, &mut tokens);
+            if let Some(first) = first_line {
+                tokens.push(Token::Line(first + line_idx));
+            }
+            tokens.push(Token::Directive(directive.trim().to_string()));
+            tokens.push(Token::Newline);
+            continue;
+        }
+
         // Strip inline comments outside quotes.
         let mut in_quote = false;
         let mut cut_pos = raw_line.len();
@@ -82,20 +247,9 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
             continue;
         }
 
-        // Indentation handling.
-        let indent = raw_line.chars().take_while(|c| *c == ' ').count();
-        match indent.cmp(indent_stack.last().unwrap()) {
-            std::cmp::Ordering::Greater => {
-                indent_stack.push(indent);
-                tokens.push(Token::Indent);
-            }
-            std::cmp::Ordering::Less => {
-                while indent < *indent_stack.last().unwrap() {
-                    indent_stack.pop();
-                    tokens.push(Token::Dedent);
-                }
-            }
-            _ => {}
+        push_indentation(raw_line, &mut indent_stack, &mut tokens);
+        if let Some(first) = first_line {
+            tokens.push(Token::Line(first + line_idx));
         }
 
         let chars: Vec<char> = trimmed.chars().collect();
@@ -107,6 +261,10 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     tokens.push(Token::Colon);
                     i += 1;
                 }
+                ',' => {
+                    tokens.push(Token::Comma);
+                    i += 1;
+                }
                 '=' if i + 1 < chars.len() && chars[i + 1] == '=' => {
                     tokens.push(Token::Equals);
                     i += 2;
@@ -120,6 +278,10 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     i += 2;
                 }
 
+                '>' if i + 1 < chars.len() && chars[i + 1] == '>' => {
+                    tokens.push(Token::Shr);
+                    i += 2;
+                }
                 '>' if i + 1 < chars.len() && chars[i + 1] == '=' => {
                     tokens.push(Token::GreaterEqual);
                     i += 2;
@@ -128,6 +290,10 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     tokens.push(Token::GreaterThan);
                     i += 1;
                 }
+                '<' if i + 1 < chars.len() && chars[i + 1] == '<' => {
+                    tokens.push(Token::Shl);
+                    i += 2;
+                }
                 '<' if i + 1 < chars.len() && chars[i + 1] == '=' => {
                     tokens.push(Token::LessEqual);
                     i += 2;
@@ -145,6 +311,10 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     tokens.push(Token::Minus);
                     i += 1;
                 }
+                '*' if i + 1 < chars.len() && chars[i + 1] == '*' => {
+                    tokens.push(Token::Power);
+                    i += 2;
+                }
                 '*' => {
                     tokens.push(Token::Star);
                     i += 1;
@@ -157,6 +327,29 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     tokens.push(Token::Percent);
                     i += 1;
                 }
+                // `&&` / `||` are not operators; the logical ones are the words `and` / `or`.
+                '&' | '|' if i + 1 < chars.len() && chars[i + 1] == chars[i] => {
+                    let word = if chars[i] == '&' { "and" } else { "or" };
+                    return Err(format!(
+                        "❌ Use `{word}` instead of `{}{}` on line {}: {}",
+                        chars[i],
+                        chars[i],
+                        line_idx + 1,
+                        raw_line
+                    ));
+                }
+                '&' => {
+                    tokens.push(Token::Amp);
+                    i += 1;
+                }
+                '|' => {
+                    tokens.push(Token::Pipe);
+                    i += 1;
+                }
+                '^' => {
+                    tokens.push(Token::Caret);
+                    i += 1;
+                }
                 '(' => {
                     tokens.push(Token::LParen);
                     i += 1;
@@ -165,6 +358,14 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     tokens.push(Token::RParen);
                     i += 1;
                 }
+                '[' => {
+                    tokens.push(Token::LBracket);
+                    i += 1;
+                }
+                ']' => {
+                    tokens.push(Token::RBracket);
+                    i += 1;
+                }
 
                 '"' => {
                     let start = i + 1;
@@ -188,6 +389,26 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     }
                 }
 
+                TRIPLE_MARK => {
+                    let start = i + 1;
+                    let len = chars[start..].iter().position(|&c| c == ';').unwrap_or(0);
+                    let idx: String = chars[start..start + len].iter().collect();
+                    let Some(content) = idx
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| triple_strings.get(n))
+                    else {
+                        return Err(format!(
+                            "❌ Unexpected character '{}' on line {}",
+                            chars[i],
+                            line_idx + 1
+                        ));
+                    };
+                    // Always a text literal, even when it ends in `.onnx`.
+                    tokens.push(Token::String(format!("\"{content}\"")));
+                    i = start + len + 1;
+                }
+
                 c if c.is_ascii_digit() => {
                     let start = i;
                     while i < chars.len() && chars[i].is_ascii_digit() {
@@ -202,25 +423,13 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
                     tokens.push(Token::Number(chars[start..i].iter().collect()));
                 }
 
-                c if c.is_alphabetic() => {
+                c if c.is_alphabetic() || c == '_' => {
                     let start = i;
                     while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
                         i += 1;
                     }
                     let word: String = chars[start..i].iter().collect();
-                    match word.to_lowercase().as_str() {
-                        "if" => tokens.push(Token::If),
-                        "elif" => tokens.push(Token::Elif),
-                        "else" => tokens.push(Token::Else),
-                        "neuro" => tokens.push(Token::Neuro),
-                        "set" => tokens.push(Token::Set),
-                        "from" => tokens.push(Token::From),
-                        "macro" => tokens.push(Token::Macro),
-                        "ai" => tokens.push(Token::AI),
-                        "and" => tokens.push(Token::And),
-                        "or" => tokens.push(Token::Or),
-                        _ => tokens.push(Token::String(word)),
-                    }
+                    tokens.push(keyword(&word).unwrap_or(Token::String(word)));
                 }
 
                 c if c.is_whitespace() => {
@@ -247,8 +456,9 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
         tokens.push(Token::Dedent);
     }
 
+    // stderr, so stdout stays script output only (e.g. for `--json`).
     if DEBUG_MODE {
-        println!("DEBUG TOKENS: {:?}", tokens);
+        eprintln!("DEBUG TOKENS: {:?}", tokens);
     }
 
     Ok(tokens)
@@ -1,6 +1,6 @@
 //! Unit tests for the NeuroChain lexer (tokenizer).
 
-use super::{tokenize, Token};
+use super::{tokenize, tokenize_with_lines, Token};
 
 #[test]
 fn tokenizes_macro_from_ai_single_line() {
@@ -72,3 +72,159 @@ fn tokenizes_parentheses() {
     assert!(toks.iter().any(|t| matches!(t, Token::LParen)));
     assert!(toks.iter().any(|t| matches!(t, Token::RParen)));
 }
+
+#[test]
+fn tokenizes_bitwise_operators_and_rejects_double_forms() {
+    let toks = tokenize("set r = a & b | c ^ d << 1 >> 2 <= 3").unwrap();
+    let ops: Vec<&Token> = toks
+        .iter()
+        .filter(|t| {
+            matches!(
+                t,
+                Token::Amp
+                    | Token::Pipe
+                    | Token::Caret
+                    | Token::Shl
+                    | Token::Shr
+                    | Token::LessEqual
+            )
+        })
+        .collect();
+    assert_eq!(
+        ops,
+        [
+            &Token::Amp,
+            &Token::Pipe,
+            &Token::Caret,
+            &Token::Shl,
+            &Token::Shr,
+            &Token::LessEqual
+        ]
+    );
+
+    let err = tokenize("set r = a && b").unwrap_err();
+    assert!(err.contains("Use `and` instead of `&&`"), "{err}");
+    let err = tokenize("set r = a || b").unwrap_err();
+    assert!(err.contains("Use `or` instead of `||`"), "{err}");
+}
+
+#[test]
+fn identifiers_may_start_with_underscore() {
+    let toks = tokenize("neuro __iter\n").unwrap();
+    assert_eq!(
+        toks,
+        vec![Token::Neuro, Token::String("__iter".into()), Token::Newline]
+    );
+}
+
+#[test]
+fn triple_quoted_string_spans_lines() {
+    let src = "set t = \"\"\"Dear {name},\n# not a comment\nthanks!\"\"\"\nneuro \"x\" # comment\nneuro \"\"\"model.onnx\"\"\"\n";
+    let toks = tokenize(src).unwrap();
+    assert_eq!(
+        toks,
+        vec![
+            Token::Set,
+            Token::String("t".into()),
+            Token::EqualsAssign,
+            Token::String("\"Dear {name},\n# not a comment\nthanks!\"".into()),
+            Token::Newline,
+            Token::Neuro,
+            Token::String("\"x\"".into()),
+            Token::Newline,
+            Token::Neuro,
+            Token::String("\"model.onnx\"".into()),
+            Token::Newline,
+        ]
+    );
+}
+
+#[test]
+fn triple_quoted_string_keeps_line_numbers_and_reports_unterminated() {
+    let err = tokenize("set t = \"\"\"a\nb\"\"\"\nneuro $\n").unwrap_err();
+    assert!(err.contains("on line 3"), "{err}");
+
+    let err = tokenize("neuro \"ok\"\nset t = \"\"\"never closed\nmore\n").unwrap_err();
+    assert!(err.contains("starting on line 2"), "{err}");
+}
+
+#[test]
+fn triple_quotes_in_comments_do_not_open_a_string() {
+    let toks =
+        tokenize("# Use \"\"\" for multi-line strings\nneuro \"hi\" // or \"\"\" here\n").unwrap();
+    assert_eq!(
+        toks,
+        vec![Token::Neuro, Token::String("\"hi\"".into()), Token::Newline]
+    );
+}
+
+#[test]
+fn triple_quotes_after_a_closed_string_still_open_one() {
+    // `"a"` closes before the `"""`, and `""` is just an empty string.
+    let toks = tokenize("neuro \"a\" + \"\"\"b\nc\"\"\"\nneuro \"\"\n").unwrap();
+    assert_eq!(
+        toks,
+        vec![
+            Token::Neuro,
+            Token::String("\"a\"".into()),
+            Token::Plus,
+            Token::String("\"b\nc\"".into()),
+            Token::Newline,
+            Token::Neuro,
+            Token::String("\"\"".into()),
+            Token::Newline,
+        ]
+    );
+    // A quote opened on one line is closed by the end of that line, not by a later `"""`.
+    let toks = tokenize("neuro \"open\nneuro \"x\"").unwrap_err();
+    assert!(toks.contains("Missing quote"), "{toks}");
+}
+
+#[test]
+fn at_comments_become_directives() {
+    let toks = tokenize(
+        "# @model sst2\n#@Model  toxic \n# plain comment\nif x:\n    # @model toxic\n    neuro x\n",
+    )
+    .unwrap();
+    assert_eq!(
+        toks,
+        vec![
+            Token::Directive("model sst2".into()),
+            Token::Newline,
+            Token::Directive("Model  toxic".into()),
+            Token::Newline,
+            Token::If,
+            Token::String("x".into()),
+            Token::Colon,
+            Token::Newline,
+            Token::Indent,
+            Token::Directive("model toxic".into()),
+            Token::Newline,
+            Token::Neuro,
+            Token::String("x".into()),
+            Token::Newline,
+            Token::Dedent,
+        ]
+    );
+}
+
+#[test]
+fn line_markers_follow_indentation_and_skip_blank_lines() {
+    let toks = tokenize_with_lines("if x:\n\n    neuro x\n", 10).unwrap();
+    assert_eq!(
+        toks,
+        vec![
+            Token::Line(10),
+            Token::If,
+            Token::String("x".into()),
+            Token::Colon,
+            Token::Newline,
+            Token::Indent,
+            Token::Line(12),
+            Token::Neuro,
+            Token::String("x".into()),
+            Token::Newline,
+            Token::Dedent,
+        ]
+    );
+}
@@ -1,6 +1,7 @@
 pub mod actions;
 pub mod ai;
 pub mod banner;
+pub mod color;
 pub mod engine;
 pub mod help_text;
 pub mod intent_stellar;
@@ -8,12 +9,17 @@ pub mod interpreter;
 pub mod lexer;
 pub mod parser;
 pub mod soroban_deep;
+#[cfg(feature = "server")]
 pub mod x402_audit;
+#[cfg(feature = "server")]
 pub mod x402_facilitator;
+#[cfg(feature = "server")]
 pub mod x402_stellar;
+#[cfg(feature = "server")]
 pub mod x402_store;
 pub mod zk_attestation;
 
 pub use engine::analyze;
+pub use interpreter::Interpreter;
 pub use lexer::tokenize;
 pub use parser::parse;
@@ -1,11 +1,14 @@
 use std::env;
 use std::fs;
 use std::io::{self, Write};
+use std::time::Instant;
 
 use neurochain::banner;
-use neurochain::engine::{analyze, analyze_blocks};
+use neurochain::color::{self, ColorChoice};
+use neurochain::engine::{analyze, analyze_blocks, check_blocks};
 use neurochain::help_text::neurochain_language_help;
 use neurochain::interpreter::Interpreter;
+use serde::Serialize;
 
 const NEUROCHAIN_VERSION: &str = env!("CARGO_PKG_VERSION");
 const NEUROCHAIN_ABOUT: &str =
@@ -23,11 +26,226 @@ fn print_help() {
     println!("{}", neurochain_language_help());
 }
 
-fn main() {
-    banner::print_banner();
+/// REPL `vars`: every variable as `name = value`, in assignment order.
+fn print_vars(interpreter: &Interpreter) {
+    if interpreter.variables.is_empty() {
+        println!("(no variables set)");
+        return;
+    }
+    for (name, value) in interpreter.variables.iter() {
+        println!("{name} = {value}");
+    }
+}
+
+/// Removes every `name` flag from `args` (the program name aside); true if there was one.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let found = args.iter().skip(1).any(|a| a == name);
+    args.retain(|a| a != name);
+    found
+}
+
+/// Removes every `flag <value>` / `flag<sep><value>` from `args` and returns the values in order;
+/// `what` names the expected value for the error when one is missing.
+fn take_values(
+    args: &mut Vec<String>,
+    flag: &str,
+    sep: &str,
+    what: &str,
+) -> Result<Vec<String>, String> {
+    let mut values = Vec::new();
+    let mut rest = Vec::with_capacity(args.len());
+    let mut it = std::mem::take(args).into_iter();
+    while let Some(arg) = it.next() {
+        match arg
+            .strip_prefix(flag)
+            .map(|tail| (tail, tail.strip_prefix(sep)))
+        {
+            Some(("", _)) => values.push(it.next().ok_or(format!("{flag} needs {what}"))?),
+            Some((_, Some(inline))) => values.push(inline.to_string()),
+            _ => rest.push(arg),
+        }
+    }
+    *args = rest;
+    Ok(values)
+}
+
+/// Pulls `-D key=value` / `-Dkey=value` pairs out of `args`.
+fn take_params(args: &mut Vec<String>) -> Result<Vec<(String, String)>, String> {
+    take_values(args, "-D", "", "key=value")?
+        .into_iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) if !k.trim().is_empty() => Ok((k.trim().to_string(), v.to_string())),
+            _ => Err(format!("-D expects key=value, got '{pair}'")),
+        })
+        .collect()
+}
+
+/// Pulls `--color <when>` / `--color=<when>` out of `args`; `when` is `always`, `never` or `auto`.
+fn take_color(args: &mut Vec<String>) -> Result<Option<ColorChoice>, String> {
+    let mut choice = None;
+    for raw in take_values(args, "--color", "=", "always, never or auto")? {
+        choice = Some(
+            ColorChoice::parse(&raw)
+                .ok_or_else(|| format!("--color expects always, never or auto, got '{raw}'"))?,
+        );
+    }
+    Ok(choice)
+}
+
+fn new_interpreter(
+    params: &[(String, String)],
+    annotate: bool,
+    continue_on_error: bool,
+) -> Interpreter {
     let mut interpreter = Interpreter::new();
+    interpreter.set_annotate(annotate);
+    interpreter.set_continue_on_error(continue_on_error);
+    for (k, v) in params {
+        interpreter.set_param(k.as_str(), v.as_str());
+    }
+    interpreter
+}
+
+/// Runs every `.nc` file in `dir` (sorted by name), each with a fresh interpreter.
+/// Failures are reported and skipped; returns the number of failed scripts.
+/// `quiet` drops the per-file headers and the summary line.
+fn run_input_dir(
+    dir: &str,
+    quiet: bool,
+    params: &[(String, String)],
+    annotate: bool,
+    continue_on_error: bool,
+) -> Result<usize, String> {
+    let mut scripts: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("Error reading directory {dir}: {e}"))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "nc"))
+        .collect();
+    scripts.sort();
+
+    let mut failed = 0;
+    for path in &scripts {
+        if !quiet {
+            println!("=== {} ===", path.display());
+        }
+        let result = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading file: {e}"))
+            .and_then(|contents| {
+                let mut interpreter = new_interpreter(params, annotate, continue_on_error);
+                analyze_blocks(&contents, &mut interpreter)
+            });
+        if let Err(err) = result {
+            eprintln!("Error in {}: {err}", path.display());
+            failed += 1;
+        }
+    }
+    if !quiet {
+        println!("Batch finished: {} run, {failed} failed.", scripts.len());
+    }
+    Ok(failed)
+}
+
+/// The `--json` result object; `ok` comes first, then `output` or `error`.
+#[derive(Serialize)]
+struct JsonRun {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl JsonRun {
+    fn failed(error: String) -> Self {
+        Self {
+            ok: false,
+            output: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// `--json`: runs the script without echoing its output, then prints one JSON object on
+/// stdout: `{"ok":true,"output":[...]}` with one string per `neuro` line, or
+/// `{"ok":false,"error":"..."}`. The framing lines go to stderr instead (unless `quiet`).
+/// Returns `ok`.
+fn run_script_json(path: &str, interpreter: &mut Interpreter, quiet: bool, time: bool) -> bool {
+    interpreter.set_echo(false);
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            let run = JsonRun::failed(format!("Error reading file: {e}"));
+            println!("{}", serde_json::to_string(&run).unwrap_or_default());
+            return false;
+        }
+    };
+    if !quiet {
+        eprintln!("Running script: {path}");
+    }
+    let started = Instant::now();
+    let result = analyze_blocks(&contents, interpreter);
+    if time {
+        let ms = started.elapsed().as_secs_f64() * 1000.0;
+        eprintln!("Execution time: {ms:.2} ms");
+    }
+    let output: Vec<String> = interpreter
+        .take_output()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let run = match result {
+        Ok(()) => {
+            if !quiet {
+                eprintln!("Script finished.");
+            }
+            JsonRun {
+                ok: true,
+                output: Some(output),
+                error: None,
+            }
+        }
+        // With `--continue-on-error` the statements that did run may have printed something.
+        Err(err) => JsonRun {
+            output: (!output.is_empty()).then_some(output),
+            ..JsonRun::failed(err)
+        },
+    };
+    println!("{}", serde_json::to_string(&run).unwrap_or_default());
+    run.ok
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    // `--quiet` / `-q` may appear anywhere: no banner and no framing lines, only script output.
+    let quiet = take_flag(&mut args, "--quiet") | take_flag(&mut args, "-q");
+    // `--time`: wall-clock duration of the script run, printed after it finishes.
+    let time = take_flag(&mut args, "--time");
+    // `--annotate`: each printed `neuro:` line starts with `[L<n>]`, its script line.
+    let annotate = take_flag(&mut args, "--annotate");
+    // `--fail-on-warning`: `--check` exits 1 on warnings too, not only on errors.
+    let fail_on_warning = take_flag(&mut args, "--fail-on-warning");
+    // `--continue-on-error`: a failing statement is reported with its line and skipped.
+    let continue_on_error = take_flag(&mut args, "--continue-on-error");
+    // `--json`: a script's output as one JSON object; no banner, framing lines on stderr.
+    let json = take_flag(&mut args, "--json");
+    let params = take_params(&mut args).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(2);
+    });
+    match take_color(&mut args) {
+        Ok(Some(choice)) => color::set_choice(choice),
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    }
+
+    if !quiet && !json {
+        banner::print_banner();
+    }
+    let mut interpreter = new_interpreter(&params, annotate, continue_on_error);
 
-    let args: Vec<String> = env::args().collect();
     if args.len() > 1 {
         let arg = &args[1];
         match arg.as_str() {
@@ -43,14 +261,73 @@ fn main() {
                 print_about();
                 return;
             }
+            // Lex + parse only: no models, no output. For CI and editors.
+            "--check" => {
+                let Some(path) = args.get(2) else {
+                    eprintln!("Usage: neurochain --check <file.nc>");
+                    std::process::exit(2);
+                };
+                let result = fs::read_to_string(path)
+                    .map_err(|e| format!("Error reading file: {e}"))
+                    .and_then(|contents| check_blocks(&contents));
+                match result {
+                    Ok(warnings) => {
+                        for warning in &warnings {
+                            eprintln!("{warning}");
+                        }
+                        if fail_on_warning && !warnings.is_empty() {
+                            std::process::exit(1);
+                        }
+                        println!("OK");
+                    }
+                    Err(err) => {
+                        eprintln!("Error: {err}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            "--input-dir" => {
+                let Some(dir) = args.get(2) else {
+                    eprintln!("Usage: neurochain --input-dir <directory>");
+                    std::process::exit(2);
+                };
+                match run_input_dir(dir, quiet, &params, annotate, continue_on_error) {
+                    Ok(0) => {}
+                    Ok(_) => std::process::exit(1),
+                    Err(err) => {
+                        eprintln!("{err}");
+                        std::process::exit(2);
+                    }
+                }
+                return;
+            }
+            _ if json => {
+                if !run_script_json(arg, &mut interpreter, quiet, time) {
+                    std::process::exit(1);
+                }
+                return;
+            }
             _ => {
                 match fs::read_to_string(arg) {
                     Ok(contents) => {
-                        println!("Running script: {arg}");
-                        match analyze_blocks(&contents, &mut interpreter) {
-                            Ok(_) => println!("Script finished."),
+                        if !quiet {
+                            println!("Running script: {arg}");
+                        }
+                        let started = Instant::now();
+                        let result = analyze_blocks(&contents, &mut interpreter);
+                        match &result {
+                            Ok(_) if !quiet => println!("Script finished."),
+                            Ok(_) => {}
                             Err(err) => eprintln!("Error: {err}"),
                         }
+                        if time {
+                            let ms = started.elapsed().as_secs_f64() * 1000.0;
+                            println!("Execution time: {ms:.2} ms");
+                        }
+                        if result.is_err() {
+                            std::process::exit(1);
+                        }
                     }
                     Err(e) => {
                         eprintln!("Error reading file: {e}");
@@ -62,16 +339,34 @@ fn main() {
     }
 
     // Interactive mode
+    // EOF (Ctrl-D / end of piped input) or a read error ends the session like `exit`,
+    // after running whatever partial block was typed.
+    let mut eof = false;
     loop {
+        if eof {
+            println!("Exiting...");
+            break;
+        }
         println!("Enter NeuroChain code (finish with an empty line):");
 
         let mut input_block = String::new();
         loop {
             print!("... ");
-            io::stdout().flush().unwrap();
+            let _ = io::stdout().flush();
 
             let mut line = String::new();
-            io::stdin().read_line(&mut line).unwrap();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) => {
+                    eof = true;
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Error reading input: {e}");
+                    eof = true;
+                    break;
+                }
+            }
 
             if line.trim().is_empty() {
                 break;
@@ -98,6 +393,15 @@ fn main() {
                 print_about();
                 continue;
             }
+            "vars" => {
+                print_vars(&interpreter);
+                continue;
+            }
+            "reset" => {
+                interpreter.reset();
+                println!("Interpreter reset: variables and models cleared.");
+                continue;
+            }
             "" => continue,
             _ => {}
         }
@@ -2,19 +2,33 @@
 //!
 //! Converts the lexer token stream into an AST.
 //! Supports model selection (`AI: "path.onnx"`), variables (`set ...`), control-flow
-//! (`if`/`elif`/`else` with indentation), and macro calls (`macro from AI: ...`).
+//! (`if`/`elif`/`else`, `while` and `for ... in range(...)` with indentation), and macro calls
+//! (`macro from AI: ...`).
 
-use std::iter::{IntoIterator, Peekable};
+use std::collections::VecDeque;
 use std::vec::IntoIter;
 
 use crate::lexer::Token;
 
+mod warnings;
+pub use warnings::{warnings, ParseWarning};
+
 /* ------------------------------- AST ------------------------------- */
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
     StringLit(String), // "Positive"
     Value(String),     // Identifier or number (a, 42).
+    Bool(bool),        // `true` / `false`
     BinaryOp(Box<Expr>, BinaryOperator, Box<Expr>),
+    Call(String, Vec<Expr>),     // Built-in call: abs_diff(a, b)
+    List(Vec<Expr>),             // ["a", "b", 3]
+    Index(Box<Expr>, Box<Expr>), // xs[0]
+    /// `match x: case "a": 1 case "b": 2 default: 0` (only as the value of `set`).
+    Match {
+        subject: Box<Expr>,
+        arms: Vec<(Expr, Expr)>,
+        default: Option<Box<Expr>>,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -23,28 +37,77 @@ pub enum BinaryOperator {
     Sub,
     Mul,
     Div,
+    FloorDiv,
     Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
     Gt,
     Lt,
     Ge,
     Le,
     Eq,
     Ne,
+    And,
+    Or,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ASTNode {
+    /// `AI: "path.onnx"`; `{var}` in the path is interpolated when the statement runs.
     AIModel(String),
-    Neuro(String), // Unified output command.
+    /// `AI: name`: the path is read from variable `name` when the statement runs.
+    AIModelFrom(String),
+    Neuro(Expr), // Unified output command: `neuro "score: " + total`.
+    /// `neuro_raw expr`: appends to the current output line without ending it.
+    NeuroRaw(Expr),
     SetVar(String, Expr),
-    SetVarFromAI(String, String),
+    /// `set var from AI ["model"]: "prompt" [else from AI "backup.onnx"]... [else "default"]`
+    SetVarFromAI {
+        var: String,
+        prompt: String,
+        /// Model ID (`"sst2"`) or `.onnx` path for this statement only; `None` = active model.
+        model: Option<String>,
+        backups: Vec<String>,
+        /// Stored (unquoted) instead of the prompt when no model gives a non-empty label.
+        default: Option<String>,
+    },
     MacroCall(String), // `macro from AI: ...`
+    /// `# @name arg`, e.g. `# @model sst2`; the argument is the rest of the line.
+    Directive {
+        name: String,
+        arg: String,
+    },
     IfStatement {
         condition: BoolExpr,
         body: Vec<ASTNode>,
         elif_blocks: Vec<(BoolExpr, Vec<ASTNode>)>,
         else_body: Option<Vec<ASTNode>>,
     },
+    WhileStatement {
+        condition: BoolExpr,
+        body: Vec<ASTNode>,
+    },
+    ForRange {
+        var: String,
+        start: Expr,
+        end: Expr,
+        body: Vec<ASTNode>,
+    },
+    /// `repeat N:` — body runs N times with `__iter` = 0..N.
+    Repeat {
+        count: i64,
+        body: Vec<ASTNode>,
+    },
+    /// Source line of the statements that follow (`tokenize_with_lines` only); used by
+    /// `--annotate`.
+    Line(usize),
+    /// A statement the parser could read but not accept (e.g. `set x = 2 3`). Kept in the tree
+    /// so callers can report it; see `first_error`.
+    ParseError(String),
 }
 
 #[derive(Debug, PartialEq)]
@@ -61,12 +124,18 @@ pub enum BoolExpr {
     LessEqual(String, String),
     And(Box<BoolExpr>, Box<BoolExpr>),
     Or(Box<BoolExpr>, Box<BoolExpr>),
+    Not(Box<BoolExpr>),
+    /// Bare value as a condition (`if ok:`); the operand keeps its quotes if it was a literal.
+    Truthy(String),
+    /// `contains(msg, "error")`: a boolean built-in. Arguments are operands like `Truthy`'s
+    /// (literals keep their quotes).
+    Predicate(String, Vec<String>),
 }
 
 /* ------------------------------ PARSER ------------------------------ */
 pub fn parse(tokens: Vec<Token>) -> Vec<ASTNode> {
     let mut ast = Vec::new();
-    let mut it = tokens.into_iter().peekable();
+    let mut it = TokenStream::new(tokens);
 
     while it.peek().is_some() {
         match parse_statement(&mut it) {
@@ -80,41 +149,120 @@ pub fn parse(tokens: Vec<Token>) -> Vec<ASTNode> {
 }
 
 /* ---------- statement ---------- */
-fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
+fn parse_statement(it: &mut TokenStream) -> Option<ASTNode> {
     match it.peek()? {
-        /* Model selection: AI: "..." */
+        /* Line marker from `tokenize_with_lines` */
+        Token::Line(n) => {
+            let n = *n;
+            it.next();
+            return Some(ASTNode::Line(n));
+        }
+
+        /* Model selection: AI: "..." or AI: var */
         Token::AI => {
             it.next();
             expect(Token::Colon, it)?;
             if let Some(Token::String(path)) = it.next() {
+                // Identifiers never contain `.`, `/` or quotes; `.onnx` literals come unquoted.
+                if path.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Some(ASTNode::AIModelFrom(path));
+                }
+                let path = match path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+                    Some(inner) => inner.to_string(),
+                    None => path,
+                };
                 return Some(ASTNode::AIModel(path));
             }
         }
 
-        /* neuro "..." */
+        /* neuro <expr> */
         Token::Neuro => {
             it.next();
-            if let Some(Token::String(text)) = it.next() {
-                return Some(ASTNode::Neuro(text));
-            }
+            let expr = parse_logic(it)?;
+            return Some(end_of_statement(ASTNode::Neuro(expr), it));
+        }
+
+        /* neuro_raw <expr> */
+        Token::NeuroRaw => {
+            it.next();
+            let expr = parse_logic(it)?;
+            return Some(end_of_statement(ASTNode::NeuroRaw(expr), it));
         }
 
         /* set ... */
         Token::Set => {
             it.next();
+            if let Some(word) = it.peek().and_then(reserved_target) {
+                while !matches!(it.peek(), None | Some(Token::Newline | Token::Dedent)) {
+                    it.next();
+                }
+                return Some(ASTNode::ParseError(format!(
+                    "❌ '{word}' is a keyword and can't be a variable name; rename it (e.g. 'my_{}')",
+                    word.to_ascii_lowercase()
+                )));
+            }
             if let Some(Token::String(var)) = it.next() {
                 match it.peek() {
                     Some(Token::EqualsAssign) => {
                         it.next();
-                        let expr = parse_expr(it)?;
-                        return Some(ASTNode::SetVar(var, expr));
+                        if starts_match(it) {
+                            let (expr, block) = parse_match(it)?;
+                            let node = ASTNode::SetVar(var, expr);
+                            // The block form already consumed its closing dedent.
+                            return Some(if block {
+                                node
+                            } else {
+                                end_of_statement(node, it)
+                            });
+                        }
+                        let expr = parse_logic(it)?;
+                        return Some(end_of_statement(ASTNode::SetVar(var, expr), it));
                     }
                     Some(Token::From) => {
                         it.next(); // from
                         expect(Token::AI, it)?;
+                        let model = match it.peek() {
+                            Some(Token::String(m)) => {
+                                let m = m.trim_matches('"').to_string();
+                                it.next();
+                                Some(m)
+                            }
+                            _ => None,
+                        };
                         expect(Token::Colon, it)?;
                         if let Some(Token::String(prompt)) = it.next() {
-                            return Some(ASTNode::SetVarFromAI(var, prompt));
+                            let mut backups = Vec::new();
+                            let mut default = None;
+                            while matches!(it.peek(), Some(Token::Else)) {
+                                it.next(); // else
+
+                                // `else "text"` is the fallback value and ends the chain.
+                                if let Some(Token::String(lit)) = it.peek() {
+                                    if lit.starts_with('"') {
+                                        default = Some(lit.trim_matches('"').to_string());
+                                        it.next();
+                                        break;
+                                    }
+                                }
+                                expect(Token::From, it)?;
+                                expect(Token::AI, it)?;
+                                if matches!(it.peek(), Some(Token::Colon)) {
+                                    it.next();
+                                }
+                                match it.next()? {
+                                    Token::String(path) => {
+                                        backups.push(path.trim_matches('"').to_string())
+                                    }
+                                    _ => return None,
+                                }
+                            }
+                            return Some(ASTNode::SetVarFromAI {
+                                var,
+                                prompt,
+                                model,
+                                backups,
+                                default,
+                            });
                         }
                     }
                     _ => {}
@@ -137,8 +285,18 @@ fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
                     Some(tok) => {
                         // Preserve original token text (string or number).
                         let txt = match tok {
-                            Token::String(s) => s.clone(),
-                            Token::Number(n) => n.clone(),
+                            // Keywords that are common words in prompts ("store 5 in x") carry
+                            // their source spelling.
+                            Token::String(s)
+                            | Token::Number(s)
+                            | Token::While(s)
+                            | Token::For(s)
+                            | Token::Repeat(s)
+                            | Token::In(s)
+                            | Token::Not(s)
+                            | Token::FloorDiv(s)
+                            | Token::True(s)
+                            | Token::False(s) => s.clone(),
                             _ => break, // Unexpected token type -> stop.
                         };
                         parts.push(txt);
@@ -162,7 +320,7 @@ fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
             let body = parse_block(it);
 
             let mut elifs = Vec::new();
-            while matches!(it.peek(), Some(Token::Elif)) {
+            while next_is(&Token::Elif, it) {
                 it.next();
                 let c = parse_bool_expr(it)?;
                 expect(Token::Colon, it)?;
@@ -172,7 +330,7 @@ fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
                 elifs.push((c, b));
             }
 
-            let else_body = if matches!(it.peek(), Some(Token::Else)) {
+            let else_body = if next_is(&Token::Else, it) {
                 it.next();
                 expect(Token::Colon, it)?;
                 skip_newlines(it);
@@ -182,6 +340,10 @@ fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
                 None
             };
 
+            let mut conditions = std::iter::once(&cond).chain(elifs.iter().map(|(c, _)| c));
+            if let Some(msg) = conditions.find_map(predicate_error) {
+                return Some(ASTNode::ParseError(msg));
+            }
             return Some(ASTNode::IfStatement {
                 condition: cond,
                 body,
@@ -190,6 +352,81 @@ fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
             });
         }
 
+        /* while ...: */
+        Token::While(_) => {
+            it.next();
+            let cond = parse_bool_expr(it)?;
+            expect(Token::Colon, it)?;
+            skip_newlines(it);
+            expect(Token::Indent, it)?;
+            let body = parse_block(it);
+
+            if let Some(msg) = predicate_error(&cond) {
+                return Some(ASTNode::ParseError(msg));
+            }
+            return Some(ASTNode::WhileStatement {
+                condition: cond,
+                body,
+            });
+        }
+
+        /* for i in range(a, b): */
+        Token::For(_) => {
+            it.next();
+            let var = match it.next()? {
+                Token::String(name) if !name.starts_with('"') => name,
+                _ => return None,
+            };
+            if !matches!(it.next()?, Token::In(_)) {
+                return None;
+            }
+            if !matches!(it.next()?, Token::String(f) if f == "range") {
+                return None;
+            }
+            expect(Token::LParen, it)?;
+            let start = parse_expr(it)?;
+            expect(Token::Comma, it)?;
+            let end = parse_expr(it)?;
+            expect(Token::RParen, it)?;
+            expect(Token::Colon, it)?;
+            skip_newlines(it);
+            expect(Token::Indent, it)?;
+            let body = parse_block(it);
+
+            return Some(ASTNode::ForRange {
+                var,
+                start,
+                end,
+                body,
+            });
+        }
+
+        /* repeat N: */
+        Token::Repeat(_) => {
+            it.next();
+            let count = match it.next()? {
+                Token::Number(n) => n.parse::<i64>().ok()?,
+                _ => return None,
+            };
+            expect(Token::Colon, it)?;
+            skip_newlines(it);
+            expect(Token::Indent, it)?;
+            let body = parse_block(it);
+
+            return Some(ASTNode::Repeat { count, body });
+        }
+
+        /* # @name arg */
+        Token::Directive(text) => {
+            let (name, arg) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+            let node = ASTNode::Directive {
+                name: name.to_ascii_lowercase(),
+                arg: arg.trim().to_string(),
+            };
+            it.next();
+            return Some(node);
+        }
+
         /* Comment-only line */
         Token::Comment => {
             it.next();
@@ -202,7 +439,7 @@ fn parse_statement(it: &mut Peekable<IntoIter<Token>>) -> Option<ASTNode> {
 }
 
 /* ---------- block ---------- */
-fn parse_block(it: &mut Peekable<IntoIter<Token>>) -> Vec<ASTNode> {
+fn parse_block(it: &mut TokenStream) -> Vec<ASTNode> {
     let mut block = Vec::new();
     loop {
         match it.peek() {
@@ -227,7 +464,7 @@ fn parse_block(it: &mut Peekable<IntoIter<Token>>) -> Vec<ASTNode> {
 }
 
 /* ---------- boolean expr ---------- */
-fn parse_bool_expr(it: &mut Peekable<IntoIter<Token>>) -> Option<BoolExpr> {
+fn parse_bool_expr(it: &mut TokenStream) -> Option<BoolExpr> {
     let mut expr = parse_bool_atom(it)?;
 
     while let Some(tok) = it.peek() {
@@ -246,20 +483,61 @@ fn parse_bool_expr(it: &mut Peekable<IntoIter<Token>>) -> Option<BoolExpr> {
     Some(expr)
 }
 
-fn parse_bool_atom(it: &mut Peekable<IntoIter<Token>>) -> Option<BoolExpr> {
-    let take_value = |it: &mut Peekable<IntoIter<Token>>| -> Option<String> {
+fn parse_bool_atom(it: &mut TokenStream) -> Option<BoolExpr> {
+    // `not` binds tighter than `and`/`or`: `not a == 1 and b == 2` negates only the first test.
+    if matches!(it.peek(), Some(Token::Not(_))) {
+        it.next();
+        return Some(BoolExpr::Not(Box::new(parse_bool_atom(it)?)));
+    }
+
+    // Grouping: `(a == "x" or b == "y") and c == "z"`.
+    if matches!(it.peek(), Some(Token::LParen)) {
+        it.next();
+        let inner = parse_bool_expr(it)?;
+        expect(Token::RParen, it)?;
+        return Some(inner);
+    }
+
+    let take_value = |it: &mut TokenStream| -> Option<String> {
         match it.next()? {
+            // `-2.5` and `- 2.5` lex the same. `-x` keeps the minus as a marker on the name;
+            // `eval_bool` negates the variable's value.
             Token::Minus => match it.next()? {
                 Token::Number(n) => Some(format!("-{}", n)),
+                Token::String(s) if !s.starts_with('"') => Some(format!("-{s}")),
                 _ => None,
             },
             Token::String(s) => Some(s),
             Token::Number(n) => Some(n),
+            // Bare `true`/`false` can't be variable names, so they always compare as literals.
+            Token::True(_) => Some("true".into()),
+            Token::False(_) => Some("false".into()),
             _ => None,
         }
     };
 
     let l = take_value(it)?;
+    // `name(arg, ...)`: a predicate call such as `contains(msg, "error")`.
+    if !l.starts_with('"') && matches!(it.peek(), Some(Token::LParen)) {
+        it.next();
+        let mut args = Vec::new();
+        if !matches!(it.peek(), Some(Token::RParen)) {
+            args.push(take_value(it)?);
+            while matches!(it.peek(), Some(Token::Comma)) {
+                it.next();
+                args.push(take_value(it)?);
+            }
+        }
+        expect(Token::RParen, it)?;
+        return Some(BoolExpr::Predicate(l, args));
+    }
+    // No comparison operator follows: `if ok:` / `if ok and other:` tests truthiness.
+    if matches!(
+        it.peek(),
+        None | Some(Token::Colon | Token::And | Token::Or | Token::RParen | Token::Newline)
+    ) {
+        return Some(BoolExpr::Truthy(l));
+    }
     let op = it.next()?;
     let r = take_value(it)?;
     let is_lit = |s: &str| s.starts_with('"') && s.ends_with('"');
@@ -292,20 +570,47 @@ fn parse_bool_atom(it: &mut Peekable<IntoIter<Token>>) -> Option<BoolExpr> {
 
 /* ---------- arithmetic expr ---------- */
 /*  EBNF
-    Expr   = Term   { ("+"|"-"|"=="|"!="|">"|"<"|">="|"<=") Term } ;
-    Term   = Factor { ("*"|"/"|"%") Factor } ;
-    Factor = Number
+    Logic  = Expr   { ("and"|"or") Expr } ;     (statement level: `set` / `neuro`)
+    Match  = "match" Expr ":" { "case" Expr ":" Logic } [ "default" ":" Logic ] ;
+                                               (`set` value only; see `parse_match`)
+    Expr   = BitOr  { ("=="|"!="|">"|"<"|">="|"<=") BitOr } ;
+    BitOr  = BitXor { "|" BitXor } ;
+    BitXor = BitAnd { "^" BitAnd } ;
+    BitAnd = Shift  { "&" Shift } ;
+    Shift  = Sum    { ("<<"|">>") Sum } ;
+    Sum    = Term   { ("+"|"-") Term } ;
+    Term   = Power  { ("*"|"/"|"div"|"%") Power } ;
+    Power  = Postfix [ "**" Power ] ;            (right-associative)
+    Postfix = Factor { "[" Expr "]" } ;
+    Factor = "-" Power
+           | Number
            | Ident
+           | Ident "(" [ Expr { "," Expr } ] ")"
            | StringLit
+           | "[" [ Expr { "," Expr } ] "]"
            | "(" Expr ")" ;
 */
-fn parse_expr(it: &mut Peekable<IntoIter<Token>>) -> Option<Expr> {
-    let mut lhs = parse_term(it)?;
+/// `a == 1 and b > 2` in `set`/`neuro`; left to right, no precedence between `and` and `or`.
+fn parse_logic(it: &mut TokenStream) -> Option<Expr> {
+    let mut lhs = parse_expr(it)?;
+    while let Some(op) = match it.peek() {
+        Some(Token::And) => Some(BinaryOperator::And),
+        Some(Token::Or) => Some(BinaryOperator::Or),
+        _ => None,
+    } {
+        it.next(); // Consume operator.
+        let rhs = parse_expr(it)?;
+        lhs = Expr::BinaryOp(Box::new(lhs), op, Box::new(rhs));
+    }
+    Some(lhs)
+}
+
+/// Comparisons bind looser than `+`/`-`: `1 == 2 + 3` is `1 == (2 + 3)`.
+fn parse_expr(it: &mut TokenStream) -> Option<Expr> {
+    let mut lhs = parse_bit_or(it)?;
 
     loop {
         let op = match it.peek()? {
-            Token::Plus => BinaryOperator::Add,
-            Token::Minus => BinaryOperator::Sub,
             Token::GreaterThan => BinaryOperator::Gt,
             Token::LessThan => BinaryOperator::Lt,
             Token::GreaterEqual => BinaryOperator::Ge,
@@ -315,32 +620,119 @@ fn parse_expr(it: &mut Peekable<IntoIter<Token>>) -> Option<Expr> {
             _ => break,
         };
         it.next(); // Consume operator.
+        let rhs = parse_bit_or(it)?;
+        lhs = Expr::BinaryOp(Box::new(lhs), op, Box::new(rhs));
+    }
+    Some(lhs)
+}
+
+/// One left-associative level of the grammar: `next { op next }`, with `op_for` naming the
+/// operators of the level.
+fn parse_left_assoc(
+    it: &mut TokenStream,
+    next: fn(&mut TokenStream) -> Option<Expr>,
+    op_for: fn(&Token) -> Option<BinaryOperator>,
+) -> Option<Expr> {
+    let mut lhs = next(it)?;
+    while let Some(op) = it.peek().and_then(op_for) {
+        it.next(); // Consume operator.
+        let rhs = next(it)?;
+        lhs = Expr::BinaryOp(Box::new(lhs), op, Box::new(rhs));
+    }
+    Some(lhs)
+}
+
+/// Bitwise operators follow Python: `|` < `^` < `&` < shifts < `+`/`-`, so `1 | 2 + 4` is
+/// `1 | (2 + 4)` and `6 & 3 == 2` compares `6 & 3`.
+fn parse_bit_or(it: &mut TokenStream) -> Option<Expr> {
+    parse_left_assoc(it, parse_bit_xor, |tok| {
+        matches!(tok, Token::Pipe).then_some(BinaryOperator::BitOr)
+    })
+}
+
+fn parse_bit_xor(it: &mut TokenStream) -> Option<Expr> {
+    parse_left_assoc(it, parse_bit_and, |tok| {
+        matches!(tok, Token::Caret).then_some(BinaryOperator::BitXor)
+    })
+}
+
+fn parse_bit_and(it: &mut TokenStream) -> Option<Expr> {
+    parse_left_assoc(it, parse_shift, |tok| {
+        matches!(tok, Token::Amp).then_some(BinaryOperator::BitAnd)
+    })
+}
+
+fn parse_shift(it: &mut TokenStream) -> Option<Expr> {
+    parse_left_assoc(it, parse_sum, |tok| match tok {
+        Token::Shl => Some(BinaryOperator::Shl),
+        Token::Shr => Some(BinaryOperator::Shr),
+        _ => None,
+    })
+}
+
+fn parse_sum(it: &mut TokenStream) -> Option<Expr> {
+    let mut lhs = parse_term(it)?;
+
+    loop {
+        let op = match it.peek()? {
+            Token::Plus => BinaryOperator::Add,
+            Token::Minus => BinaryOperator::Sub,
+            _ => break,
+        };
+        it.next(); // Consume operator.
         let rhs = parse_term(it)?;
         lhs = Expr::BinaryOp(Box::new(lhs), op, Box::new(rhs));
     }
     Some(lhs)
 }
 
-fn parse_term(it: &mut Peekable<IntoIter<Token>>) -> Option<Expr> {
-    let mut lhs = parse_factor(it)?;
+fn parse_term(it: &mut TokenStream) -> Option<Expr> {
+    let mut lhs = parse_power(it)?;
 
     while let Some(op) = match it.peek()? {
         Token::Star => Some(BinaryOperator::Mul),
         Token::Slash => Some(BinaryOperator::Div),
+        Token::FloorDiv(_) => Some(BinaryOperator::FloorDiv),
         Token::Percent => Some(BinaryOperator::Mod),
         _ => None,
     } {
         it.next(); // Consume operator.
-        let rhs = parse_factor(it)?;
+        let rhs = parse_power(it)?;
         lhs = Expr::BinaryOp(Box::new(lhs), op, Box::new(rhs));
     }
     Some(lhs)
 }
 
-fn parse_factor(it: &mut Peekable<IntoIter<Token>>) -> Option<Expr> {
+fn parse_power(it: &mut TokenStream) -> Option<Expr> {
+    let base = parse_postfix(it)?;
+    if !matches!(it.peek(), Some(Token::Power)) {
+        return Some(base);
+    }
+    it.next(); // Consume `**`.
+    let exp = parse_power(it)?; // Recurse on the right: 2 ** 3 ** 2 == 2 ** 9.
+    Some(Expr::BinaryOp(
+        Box::new(base),
+        BinaryOperator::Pow,
+        Box::new(exp),
+    ))
+}
+
+fn parse_postfix(it: &mut TokenStream) -> Option<Expr> {
+    let mut expr = parse_factor(it)?;
+    while matches!(it.peek(), Some(Token::LBracket)) {
+        it.next(); // Consume `[`.
+        let index = parse_expr(it)?;
+        expect(Token::RBracket, it)?;
+        expr = Expr::Index(Box::new(expr), Box::new(index));
+    }
+    Some(expr)
+}
+
+fn parse_factor(it: &mut TokenStream) -> Option<Expr> {
     match it.next()? {
         Token::Minus => {
-            let inner = parse_factor(it)?;
+            // Unary minus applies to the whole power: -2 ** 2 == -(2 ** 2).
+            let inner = parse_power(it)?;
             Some(Expr::BinaryOp(
                 Box::new(Expr::Value("0".into())),
                 BinaryOperator::Sub,
@@ -348,11 +740,40 @@ fn parse_factor(it: &mut Peekable<IntoIter<Token>>) -> Option<Expr> {
             ))
         }
         Token::Number(n) => Some(Expr::Value(n)),
+        Token::True(_) => Some(Expr::Bool(true)),
+        Token::False(_) => Some(Expr::Bool(false)),
         Token::String(s) if s.starts_with('"') && s.ends_with('"') => {
             Some(Expr::StringLit(s.trim_matches('"').to_string()))
         }
+        Token::String(s) if matches!(it.peek(), Some(Token::LParen)) => {
+            it.next();
+            let mut args = Vec::new();
+            if !matches!(it.peek(), Some(Token::RParen)) {
+                args.push(parse_expr(it)?);
+                while matches!(it.peek(), Some(Token::Comma)) {
+                    it.next();
+                    args.push(parse_expr(it)?);
+                }
+            }
+            expect(Token::RParen, it)?;
+            Some(Expr::Call(s, args))
+        }
         Token::String(s) => Some(Expr::Value(s)),
 
+        // List literal.
+        Token::LBracket => {
+            let mut items = Vec::new();
+            if !matches!(it.peek(), Some(Token::RBracket)) {
+                items.push(parse_expr(it)?);
+                while matches!(it.peek(), Some(Token::Comma)) {
+                    it.next();
+                    items.push(parse_expr(it)?);
+                }
+            }
+            expect(Token::RBracket, it)?;
+            Some(Expr::List(items))
+        }
+
         // Parentheses.
         Token::LParen => {
             let inner = parse_expr(it)?; // Recursive.
@@ -363,13 +784,212 @@ fn parse_factor(it: &mut Peekable<IntoIter<Token>>) -> Option<Expr> {
     }
 }
 
+/* ---------- match expr ---------- */
+/// `match` is only a keyword here: the word followed by a value (`match cmd`). A variable named
+/// `match` keeps working everywhere else.
+fn starts_match(it: &mut TokenStream) -> bool {
+    matches!(it.peek(), Some(Token::String(w)) if w.eq_ignore_ascii_case("match"))
+        && matches!(
+            it.peek_nth(1),
+            Some(Token::String(_) | Token::Number(_) | Token::LParen)
+        )
+}
+
+/// `match Expr ":" { "case" Expr ":" Logic } [ "default" ":" Logic ]`, either on one line or
+/// with the arms indented on the lines below. Returns the expression and whether it was the
+/// block form.
+fn parse_match(it: &mut TokenStream) -> Option<(Expr, bool)> {
+    it.next(); // Consume `match`.
+    let subject = parse_expr(it)?;
+    expect(Token::Colon, it)?;
+    let block = matches!(it.peek(), Some(Token::Newline));
+    if block {
+        it.next();
+        expect(Token::Indent, it)?;
+    }
+
+    let word = |it: &mut TokenStream, w: &str| matches!(it.peek(), Some(Token::String(s)) if s.eq_ignore_ascii_case(w));
+    let mut arms = Vec::new();
+    let mut default = None;
+    loop {
+        if block {
+            skip_newlines(it);
+        }
+        if default.is_some() {
+            break; // `default` is the last arm.
+        }
+        if word(it, "case") {
+            it.next();
+            let pattern = parse_expr(it)?;
+            expect(Token::Colon, it)?;
+            arms.push((pattern, parse_logic(it)?));
+        } else if word(it, "default") {
+            it.next();
+            expect(Token::Colon, it)?;
+            default = Some(Box::new(parse_logic(it)?));
+        } else {
+            break;
+        }
+    }
+    if block {
+        expect(Token::Dedent, it)?;
+    }
+    if arms.is_empty() && default.is_none() {
+        return None;
+    }
+    let expr = Expr::Match {
+        subject: Box::new(subject),
+        arms,
+        default,
+    };
+    Some((expr, block))
+}
+
 /* ---------- util ---------- */
-fn skip_newlines(it: &mut Peekable<IntoIter<Token>>) {
-    while matches!(it.peek(), Some(Token::Newline)) {
+/// The tokens still to parse, with lookahead past the next one (`elif` behind a `Line` marker,
+/// `match` followed by its subject) that only buffers what it peeks at.
+struct TokenStream {
+    rest: IntoIter<Token>,
+    ahead: VecDeque<Token>,
+}
+
+impl TokenStream {
+    fn new(tokens: Vec<Token>) -> Self {
+        TokenStream {
+            rest: tokens.into_iter(),
+            ahead: VecDeque::new(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    /// The token `n` places ahead; `peek_nth(0)` is `peek`.
+    fn peek_nth(&mut self, n: usize) -> Option<&Token> {
+        while self.ahead.len() <= n {
+            let tok = self.rest.next()?;
+            self.ahead.push_back(tok);
+        }
+        self.ahead.get(n)
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.ahead.pop_front().or_else(|| self.rest.next())
+    }
+}
+
+/// An expression statement must end the line. Anything left over is skipped up to the end of
+/// the line and reported instead of being dropped token by token.
+fn end_of_statement(node: ASTNode, it: &mut TokenStream) -> ASTNode {
+    let ends = |t: Option<&Token>| matches!(t, None | Some(Token::Newline | Token::Dedent));
+    let Some(tok) = it.peek().filter(|t| !ends(Some(t))) else {
+        return node;
+    };
+    let shown = match tok {
+        Token::String(s) | Token::Number(s) => s.clone(),
+        other => format!("{other:?}"),
+    };
+    while !ends(it.peek()) {
+        it.next();
+    }
+    ASTNode::ParseError(format!("❌ Unexpected token '{shown}' after expression"))
+}
+
+/// Keywords used as variable names (`set AI = ...`, `set in = 3`); `set` reports these by name
+/// instead of dropping the statement or failing as a generic parse error.
+fn reserved_target(tok: &Token) -> Option<&'static str> {
+    Some(match tok {
+        Token::AI => "AI",
+        Token::Neuro => "neuro",
+        Token::NeuroRaw => "neuro_raw",
+        Token::Macro => "macro",
+        Token::If => "if",
+        Token::Elif => "elif",
+        Token::Else => "else",
+        Token::While(_) => "while",
+        Token::For(_) => "for",
+        Token::In(_) => "in",
+        Token::Repeat(_) => "repeat",
+        Token::Set => "set",
+        Token::From => "from",
+        Token::And => "and",
+        Token::Or => "or",
+        Token::Not(_) => "not",
+        Token::FloorDiv(_) => "div",
+        Token::True(_) => "true",
+        Token::False(_) => "false",
+        _ => return None,
+    })
+}
+
+/// Why a predicate call in `cond` can't run: an unknown name (a typo such as `contans`) or a
+/// wrong argument count. Reported as a parse error rather than evaluating to false.
+fn predicate_error(cond: &BoolExpr) -> Option<String> {
+    match cond {
+        BoolExpr::And(l, r) | BoolExpr::Or(l, r) => {
+            predicate_error(l).or_else(|| predicate_error(r))
+        }
+        BoolExpr::Not(inner) => predicate_error(inner),
+        BoolExpr::Predicate(name, _)
+            if !["contains", "startswith", "endswith"].contains(&name.as_str()) =>
+        {
+            Some(format!(
+                "❌ Unknown condition function '{name}' (expected contains, startswith or endswith)"
+            ))
+        }
+        BoolExpr::Predicate(name, args) if args.len() != 2 => {
+            Some(format!("❌ {name}() takes 2 arguments, got {}", args.len()))
+        }
+        _ => None,
+    }
+}
+
+/// First `ParseError` in `ast`, including nested blocks.
+pub fn first_error(ast: &[ASTNode]) -> Option<&str> {
+    ast.iter().find_map(|node| match node {
+        ASTNode::ParseError(msg) => Some(msg.as_str()),
+        ASTNode::IfStatement {
+            body,
+            elif_blocks,
+            else_body,
+            ..
+        } => first_error(body)
+            .or_else(|| elif_blocks.iter().find_map(|(_, b)| first_error(b)))
+            .or_else(|| else_body.as_deref().and_then(first_error)),
+        ASTNode::WhileStatement { body, .. }
+        | ASTNode::ForRange { body, .. }
+        | ASTNode::Repeat { body, .. } => first_error(body),
+        _ => None,
+    })
+}
+
+/// Also skips line markers: the places that skip blank lines either expect an `Indent` next
+/// (which comes before a marker) or are inside an expression (`match` arms).
+fn skip_newlines(it: &mut TokenStream) {
+    while matches!(it.peek(), Some(Token::Newline | Token::Line(_))) {
+        it.next();
+    }
+}
+
+/// Whether `tok` is next, looking past a line marker. The marker is consumed only on a match,
+/// so it stays in front of the following statement otherwise.
+fn next_is(tok: &Token, it: &mut TokenStream) -> bool {
+    let marker = matches!(it.peek(), Some(Token::Line(_)));
+    let next = if marker { it.peek_nth(1) } else { it.peek() };
+    if next != Some(tok) {
+        return false;
+    }
+    if marker {
         it.next();
     }
+    true
 }
-fn expect(tok: Token, it: &mut Peekable<IntoIter<Token>>) -> Option<()> {
+fn expect(tok: Token, it: &mut TokenStream) -> Option<()> {
     matches!(it.next(), Some(t) if t == tok).then(|| ())
 }
 
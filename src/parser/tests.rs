@@ -41,3 +41,695 @@ else:
         "expected an if/else statement"
     );
 }
+
+#[test]
+fn parses_while_block() {
+    let src = r#"
+set i = 0
+while i < 3:
+    neuro i
+    set i = i + 1
+neuro "done"
+"#;
+    let toks = tokenize(src).unwrap();
+    let ast = parse(toks);
+    assert_eq!(ast.len(), 3);
+    match &ast[1] {
+        ASTNode::WhileStatement { condition, body } => {
+            assert_eq!(condition, &BoolExpr::Less("i".into(), "3".into()));
+            assert_eq!(body.len(), 2);
+        }
+        other => panic!("expected while statement, got {other:?}"),
+    }
+}
+
+#[test]
+fn parses_for_range_block() {
+    let src = "for i in range(0, n + 1):\n    neuro i\n";
+    let ast = parse(tokenize(src).unwrap());
+    assert_eq!(ast.len(), 1);
+    match &ast[0] {
+        ASTNode::ForRange {
+            var,
+            start,
+            end,
+            body,
+        } => {
+            assert_eq!(var, "i");
+            assert_eq!(start, &Expr::Value("0".into()));
+            assert!(matches!(end, Expr::BinaryOp(..)));
+            assert_eq!(body.len(), 1);
+        }
+        other => panic!("expected for-range statement, got {other:?}"),
+    }
+}
+
+#[test]
+fn parses_repeat_block() {
+    let ast = parse(tokenize("repeat 3:\n    neuro \"hi\"\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::Repeat {
+            count: 3,
+            body: vec![ASTNode::Neuro(Expr::StringLit("hi".into()))],
+        }]
+    );
+
+    // Unquoted prompts keep `repeat` as a word, spelled as written.
+    let ast = parse(tokenize("macro from AI: Repeat hello 2 times\n").unwrap());
+    assert_eq!(ast, vec![ASTNode::MacroCall("Repeat hello 2 times".into())]);
+}
+
+#[test]
+fn macro_prompt_keeps_loop_keywords_as_words() {
+    let ast = parse(tokenize("macro from AI: Store 5 in x\n").unwrap());
+    assert_eq!(ast, vec![ASTNode::MacroCall("Store 5 in x".into())]);
+
+    let ast = parse(tokenize("macro from AI: Repeat True story\n").unwrap());
+    assert_eq!(ast, vec![ASTNode::MacroCall("Repeat True story".into())]);
+}
+
+#[test]
+fn parses_function_call_arguments() {
+    let ast = parse(tokenize("set m = avg(a, 2 + 3)\n").unwrap());
+    match &ast[0] {
+        ASTNode::SetVar(name, Expr::Call(func, args)) => {
+            assert_eq!(name, "m");
+            assert_eq!(func, "avg");
+            assert_eq!(args.len(), 2);
+            assert_eq!(args[0], Expr::Value("a".into()));
+        }
+        other => panic!("expected call expression, got {other:?}"),
+    }
+}
+
+#[test]
+fn not_applies_to_the_nearest_comparison() {
+    let ast = parse(tokenize("if not a == 1 or b == 2:\n    neuro \"x\"\n").unwrap());
+    match &ast[0] {
+        ASTNode::IfStatement { condition, .. } => assert_eq!(
+            condition,
+            &BoolExpr::Or(
+                Box::new(BoolExpr::Not(Box::new(BoolExpr::VarEqualsVar(
+                    "a".into(),
+                    "1".into()
+                )))),
+                Box::new(BoolExpr::VarEqualsVar("b".into(), "2".into())),
+            )
+        ),
+        other => panic!("expected if statement, got {other:?}"),
+    }
+}
+
+fn if_condition(src: &str) -> BoolExpr {
+    match parse(tokenize(src).unwrap()).remove(0) {
+        ASTNode::IfStatement { condition, .. } => condition,
+        other => panic!("expected if statement, got {other:?}"),
+    }
+}
+
+#[test]
+fn predicate_calls_are_condition_atoms() {
+    assert_eq!(
+        if_condition(
+            "if contains(msg, \"error\") and not endswith(msg, \"ok\"):\n    neuro \"x\"\n"
+        ),
+        BoolExpr::And(
+            Box::new(BoolExpr::Predicate(
+                "contains".into(),
+                vec!["msg".into(), "\"error\"".into()]
+            )),
+            Box::new(BoolExpr::Not(Box::new(BoolExpr::Predicate(
+                "endswith".into(),
+                vec!["msg".into(), "\"ok\"".into()]
+            ))))
+        )
+    );
+}
+
+#[test]
+fn unknown_predicates_and_wrong_arity_are_parse_errors() {
+    let err = |src: &str| first_error(&parse(tokenize(src).unwrap())).map(str::to_string);
+    assert_eq!(
+        err("if contans(msg, \"x\"):\n    neuro \"y\"\n").as_deref(),
+        Some("❌ Unknown condition function 'contans' (expected contains, startswith or endswith)")
+    );
+    assert_eq!(
+        err("while ok and not startswith(msg):\n    neuro \"y\"\n").as_deref(),
+        Some("❌ startswith() takes 2 arguments, got 1")
+    );
+    assert!(
+        err("if a == 1:\n    neuro \"y\"\nelif endswith(msg, \"!\", 3):\n    neuro \"z\"\n")
+            .is_some()
+    );
+    assert_eq!(err("if contains(msg, \"x\"):\n    neuro \"y\"\n"), None);
+}
+
+#[test]
+fn negative_operands_in_conditions() {
+    assert_eq!(
+        if_condition("if temp < - 2.5:\n    neuro \"cold\"\n"),
+        BoolExpr::Less("temp".into(), "-2.5".into())
+    );
+    assert_eq!(
+        if_condition("if -x > 0:\n    neuro \"neg\"\n"),
+        BoolExpr::Greater("-x".into(), "0".into())
+    );
+    assert_eq!(
+        if_condition("if a == -b:\n    neuro \"opposite\"\n"),
+        BoolExpr::VarEqualsVar("a".into(), "-b".into())
+    );
+}
+
+#[test]
+fn parenthesized_condition_changes_grouping() {
+    let a = || Box::new(BoolExpr::EqualsVar("a".into(), "x".into()));
+    let b = || Box::new(BoolExpr::EqualsVar("b".into(), "y".into()));
+    let c = || Box::new(BoolExpr::EqualsVar("c".into(), "z".into()));
+
+    let flat = if_condition("if a == \"x\" or b == \"y\" and c == \"z\":\n    neuro \"ok\"\n");
+    assert_eq!(flat, BoolExpr::And(Box::new(BoolExpr::Or(a(), b())), c()));
+
+    let grouped = if_condition("if a == \"x\" or (b == \"y\" and c == \"z\"):\n    neuro \"ok\"\n");
+    assert_eq!(
+        grouped,
+        BoolExpr::Or(a(), Box::new(BoolExpr::And(b(), c())))
+    );
+    assert_ne!(flat, grouped);
+}
+
+#[test]
+fn nested_and_negated_groups_parse() {
+    let cond = if_condition("if not ((a == 1) or b == 2):\n    neuro \"ok\"\n");
+    assert_eq!(
+        cond,
+        BoolExpr::Not(Box::new(BoolExpr::Or(
+            Box::new(BoolExpr::VarEqualsVar("a".into(), "1".into())),
+            Box::new(BoolExpr::VarEqualsVar("b".into(), "2".into())),
+        )))
+    );
+}
+
+#[test]
+fn neuro_parses_an_expression() {
+    let ast = parse(tokenize("neuro \"score: \" + total\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::Neuro(Expr::BinaryOp(
+            Box::new(Expr::StringLit("score: ".into())),
+            BinaryOperator::Add,
+            Box::new(Expr::Value("total".into())),
+        ))]
+    );
+}
+
+#[test]
+fn power_is_right_associative() {
+    let ast = parse(tokenize("set r = 2 ** 3 ** 2\n").unwrap());
+    let pow = |l: Expr, r: Expr| Expr::BinaryOp(Box::new(l), BinaryOperator::Pow, Box::new(r));
+    let v = |n: &str| Expr::Value(n.into());
+    assert_eq!(
+        ast,
+        vec![ASTNode::SetVar(
+            "r".into(),
+            pow(v("2"), pow(v("3"), v("2")))
+        )]
+    );
+}
+
+#[test]
+fn additive_binds_tighter_than_comparison() {
+    let op = |l: Expr, o: BinaryOperator, r: Expr| Expr::BinaryOp(Box::new(l), o, Box::new(r));
+    let v = |n: &str| Expr::Value(n.into());
+
+    let ast = parse(tokenize("set r = 1 == 2 + 3\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::SetVar(
+            "r".into(),
+            op(
+                v("1"),
+                BinaryOperator::Eq,
+                op(v("2"), BinaryOperator::Add, v("3"))
+            )
+        )]
+    );
+
+    let ast = parse(tokenize("set r = 1 + 2 == 3 - 0\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::SetVar(
+            "r".into(),
+            op(
+                op(v("1"), BinaryOperator::Add, v("2")),
+                BinaryOperator::Eq,
+                op(v("3"), BinaryOperator::Sub, v("0"))
+            )
+        )]
+    );
+}
+
+#[test]
+fn bitwise_operators_follow_python_precedence() {
+    let op = |l: Expr, o: BinaryOperator, r: Expr| Expr::BinaryOp(Box::new(l), o, Box::new(r));
+    let v = |n: &str| Expr::Value(n.into());
+
+    // `|` < `^` < `&` < shifts < `+`.
+    let ast = parse(tokenize("set r = 1 | 2 ^ 3 & 4 << 5 + 6\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::SetVar(
+            "r".into(),
+            op(
+                v("1"),
+                BinaryOperator::BitOr,
+                op(
+                    v("2"),
+                    BinaryOperator::BitXor,
+                    op(
+                        v("3"),
+                        BinaryOperator::BitAnd,
+                        op(
+                            v("4"),
+                            BinaryOperator::Shl,
+                            op(v("5"), BinaryOperator::Add, v("6"))
+                        )
+                    )
+                )
+            )
+        )]
+    );
+
+    // Comparisons bind looser, and each level is left-associative.
+    let ast = parse(tokenize("set r = 8 >> 1 >> 1 == 2\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::SetVar(
+            "r".into(),
+            op(
+                op(
+                    op(v("8"), BinaryOperator::Shr, v("1")),
+                    BinaryOperator::Shr,
+                    v("1")
+                ),
+                BinaryOperator::Eq,
+                v("2")
+            )
+        )]
+    );
+}
+
+#[test]
+fn set_from_ai_parses_backup_models_in_order() {
+    let src =
+        "set x from AI: \"hello\" else from AI: \"a/model.onnx\" else from AI \"b/model.onnx\"\n";
+    let ast = parse(tokenize(src).unwrap());
+    assert_eq!(
+        ast,
+        vec![ASTNode::SetVarFromAI {
+            var: "x".into(),
+            prompt: "\"hello\"".into(),
+            model: None,
+            backups: vec!["a/model.onnx".into(), "b/model.onnx".into()],
+            default: None,
+        }]
+    );
+}
+
+#[test]
+fn bare_value_condition_parses_as_truthy() {
+    assert_eq!(
+        if_condition("if ok:\n    neuro \"yes\"\n"),
+        BoolExpr::Truthy("ok".into())
+    );
+    assert_eq!(
+        if_condition("if ok and x == \"1\":\n    neuro \"yes\"\n"),
+        BoolExpr::And(
+            Box::new(BoolExpr::Truthy("ok".into())),
+            Box::new(BoolExpr::EqualsVar("x".into(), "1".into()))
+        )
+    );
+}
+
+#[test]
+fn nested_if_with_own_else_stays_inside_outer_body() {
+    let src = "if a==1:\n  if b==2:\n    neuro \"x\"\n  elif b==3:\n    neuro \"z\"\n  else:\n    neuro \"y\"\nneuro \"after\"\n";
+    let ast = parse(tokenize(src).unwrap());
+    let lit = |s: &str| ASTNode::Neuro(Expr::StringLit(s.into()));
+    let eq = |a: &str, b: &str| BoolExpr::VarEqualsVar(a.into(), b.into());
+    assert_eq!(
+        ast,
+        vec![
+            ASTNode::IfStatement {
+                condition: eq("a", "1"),
+                body: vec![ASTNode::IfStatement {
+                    condition: eq("b", "2"),
+                    body: vec![lit("x")],
+                    elif_blocks: vec![(eq("b", "3"), vec![lit("z")])],
+                    else_body: Some(vec![lit("y")]),
+                }],
+                elif_blocks: vec![],
+                else_body: None,
+            },
+            lit("after"),
+        ]
+    );
+}
+
+#[test]
+fn true_and_false_parse_as_bool_literals() {
+    let ast = parse(tokenize("set b = true\nset c = FALSE\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![
+            ASTNode::SetVar("b".into(), Expr::Bool(true)),
+            ASTNode::SetVar("c".into(), Expr::Bool(false)),
+        ]
+    );
+    assert_eq!(
+        if_condition("if b == true:\n    neuro \"yes\"\n"),
+        BoolExpr::VarEqualsVar("b".into(), "true".into())
+    );
+}
+
+#[test]
+fn list_literals_and_indexing() {
+    let ast = parse(tokenize("set xs = [\"a\", 2, []]\nneuro xs[i + 1][0]\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![
+            ASTNode::SetVar(
+                "xs".into(),
+                Expr::List(vec![
+                    Expr::StringLit("a".into()),
+                    Expr::Value("2".into()),
+                    Expr::List(Vec::new()),
+                ])
+            ),
+            ASTNode::Neuro(Expr::Index(
+                Box::new(Expr::Index(
+                    Box::new(Expr::Value("xs".into())),
+                    Box::new(Expr::BinaryOp(
+                        Box::new(Expr::Value("i".into())),
+                        BinaryOperator::Add,
+                        Box::new(Expr::Value("1".into())),
+                    )),
+                )),
+                Box::new(Expr::Value("0".into())),
+            )),
+        ]
+    );
+}
+
+#[test]
+fn warnings_flag_empty_blocks_and_prompts() {
+    let src = "if x == 1:\nneuro \"a\"\nset m from AI: \"\"\nset n from AI \"sst2\":\nwhile x < 3:\n    neuro x\nelse_count = 1\n";
+    let found = warnings(&crate::lexer::tokenize_with_lines(src, 10).unwrap());
+    let shown: Vec<String> = found.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        shown,
+        [
+            "⚠️ line 10: empty `if` block (nothing indented under it)",
+            "⚠️ line 12: empty prompt after `from AI:`",
+            "⚠️ line 13: empty prompt after `from AI:`",
+        ]
+    );
+
+    let clean = "if x == 1:\n    neuro \"a\"\nelse:\n    neuro \"b\"\nset m from AI: \"hi\"\n";
+    assert!(warnings(&tokenize(clean).unwrap()).is_empty());
+}
+
+#[test]
+fn ai_line_takes_a_variable_name_or_a_literal_path() {
+    assert_eq!(
+        parse(tokenize("AI: model_path\n").unwrap()),
+        vec![ASTNode::AIModelFrom("model_path".into())]
+    );
+    assert_eq!(
+        parse(tokenize("AI: \"models/{name}/model.onnx\"\n").unwrap()),
+        vec![ASTNode::AIModel("models/{name}/model.onnx".into())]
+    );
+    assert_eq!(
+        parse(tokenize("AI: \"{path}\"\n").unwrap()),
+        vec![ASTNode::AIModel("{path}".into())]
+    );
+}
+
+#[test]
+fn ai_line_allows_trailing_comments() {
+    for src in [
+        "AI: \"models/x.onnx\" # use the quantized one\n",
+        "AI: \"models/x.onnx\"   // same with slashes\n",
+        "AI: \"models/x.onnx\"# no space\n",
+    ] {
+        assert_eq!(
+            parse(tokenize(src).unwrap()),
+            vec![ASTNode::AIModel("models/x.onnx".into())],
+            "{src:?}"
+        );
+    }
+    // Comment markers inside the quoted path are part of the path.
+    assert_eq!(
+        parse(tokenize("AI: \"models/v#2/model.onnx\" # comment\n").unwrap()),
+        vec![ASTNode::AIModel("models/v#2/model.onnx".into())]
+    );
+}
+
+#[test]
+fn trailing_tokens_after_expressions_are_parse_errors() {
+    for (src, shown) in [
+        ("set x = 2 3\n", "'3'"),
+        ("neuro \"a\" b\n", "'b'"),
+        ("set y = (1 + 2) )\n", "RParen"),
+    ] {
+        let ast = parse(tokenize(src).unwrap());
+        assert!(
+            matches!(ast.as_slice(), [ASTNode::ParseError(msg)] if msg.contains(shown)),
+            "{src:?} -> {ast:?}"
+        );
+    }
+
+    // The rest of the line is skipped, so parsing resumes on the next line.
+    let ast = parse(tokenize("if 1 == 1:\n    set x = 1 2\nneuro \"after\"\n").unwrap());
+    assert_eq!(
+        first_error(&ast),
+        Some("❌ Unexpected token '2' after expression")
+    );
+    assert_eq!(
+        ast.last(),
+        Some(&ASTNode::Neuro(Expr::StringLit("after".into())))
+    );
+    assert_eq!(
+        first_error(&parse(tokenize("set x = 2 + 3\n").unwrap())),
+        None
+    );
+}
+
+#[test]
+fn neuro_takes_a_bare_number() {
+    assert_eq!(
+        parse(tokenize("neuro 42\n").unwrap()),
+        vec![ASTNode::Neuro(Expr::Value("42".into()))]
+    );
+    assert_eq!(
+        parse(tokenize("neuro_raw 1.50\n").unwrap()),
+        vec![ASTNode::NeuroRaw(Expr::Value("1.50".into()))]
+    );
+}
+
+#[test]
+fn keyword_as_set_target_suggests_a_rename() {
+    let ast = parse(tokenize("set neuro = \"x\"\nneuro \"after\"\n").unwrap());
+    assert_eq!(
+        first_error(&ast),
+        Some("❌ 'neuro' is a keyword and can't be a variable name; rename it (e.g. 'my_neuro')")
+    );
+    assert!(!first_error(&ast).unwrap().contains("Unexpected token"));
+    assert_eq!(
+        ast.last(),
+        Some(&ASTNode::Neuro(Expr::StringLit("after".into())))
+    );
+
+    let ast = parse(tokenize("set AI = \"x\"\n").unwrap());
+    assert!(first_error(&ast).is_some_and(|msg| msg.contains("'AI' is a keyword")));
+
+    // Words that became keywords later (loops, `not`, `div`, booleans) are reported too.
+    assert_eq!(
+        first_error(&parse(tokenize("set in = 3\n").unwrap())),
+        Some("❌ 'in' is a keyword and can't be a variable name; rename it (e.g. 'my_in')")
+    );
+    for word in ["for", "while", "repeat", "not", "div", "true", "False"] {
+        let ast = parse(tokenize(&format!("set {word} = 1\n")).unwrap());
+        let expected = format!("'{}' is a keyword", word.to_lowercase());
+        assert!(
+            first_error(&ast).is_some_and(|msg| msg.contains(&expected)),
+            "{word}: {ast:?}"
+        );
+    }
+}
+
+#[test]
+fn set_from_ai_parses_an_else_default_tail() {
+    let src = "set mood from AI: \"text\" else \"Neutral\"\nset t from AI: \"hi\" else from AI \"b.onnx\" else \"none\"\n";
+    let ast = parse(tokenize(src).unwrap());
+    assert_eq!(
+        ast,
+        vec![
+            ASTNode::SetVarFromAI {
+                var: "mood".into(),
+                prompt: "\"text\"".into(),
+                model: None,
+                backups: Vec::new(),
+                default: Some("Neutral".into()),
+            },
+            ASTNode::SetVarFromAI {
+                var: "t".into(),
+                prompt: "\"hi\"".into(),
+                model: None,
+                backups: vec!["b.onnx".into()],
+                default: Some("none".into()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn set_from_ai_accepts_an_inline_model() {
+    let src = "set mood from AI \"sst2\": \"great\"\nset t from AI \"m/x.onnx\": \"hi\" else from AI \"b.onnx\"\n";
+    let ast = parse(tokenize(src).unwrap());
+    assert_eq!(
+        ast,
+        vec![
+            ASTNode::SetVarFromAI {
+                var: "mood".into(),
+                prompt: "\"great\"".into(),
+                model: Some("sst2".into()),
+                backups: Vec::new(),
+                default: None,
+            },
+            ASTNode::SetVarFromAI {
+                var: "t".into(),
+                prompt: "\"hi\"".into(),
+                model: Some("m/x.onnx".into()),
+                backups: vec!["b.onnx".into()],
+                default: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn match_expression_inline_and_block_forms() {
+    let expected = Expr::Match {
+        subject: Box::new(Expr::Value("cmd".into())),
+        arms: vec![(Expr::StringLit("A".into()), Expr::StringLit("alpha".into()))],
+        default: Some(Box::new(Expr::StringLit("other".into()))),
+    };
+
+    let inline = "set msg = match cmd: case \"A\": \"alpha\" default: \"other\"\nneuro msg\n";
+    let block =
+        "set msg = match cmd:\n    case \"A\": \"alpha\"\n    default: \"other\"\nneuro msg\n";
+    for src in [inline, block] {
+        let ast = parse(tokenize(src).unwrap());
+        assert_eq!(
+            ast,
+            vec![
+                ASTNode::SetVar("msg".into(), expected.clone()),
+                ASTNode::Neuro(Expr::Value("msg".into())),
+            ],
+            "{src}"
+        );
+    }
+
+    // Without a value after it, `match` is an ordinary variable name.
+    let ast = parse(tokenize("set match = 1\nset y = match\n").unwrap());
+    assert_eq!(
+        ast[1],
+        ASTNode::SetVar("y".into(), Expr::Value("match".into()))
+    );
+}
+
+#[test]
+fn directives_split_name_and_argument() {
+    let ast = parse(tokenize("#@Model  sst2 \n# @strict\n").unwrap());
+    assert_eq!(
+        ast,
+        vec![
+            ASTNode::Directive {
+                name: "model".into(),
+                arg: "sst2".into(),
+            },
+            ASTNode::Directive {
+                name: "strict".into(),
+                arg: String::new(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn line_markers_do_not_break_elif_else() {
+    let src = "if a == 1:\n    neuro \"one\"\nelif a == 2:\n    neuro \"two\"\nelse:\n    neuro \"other\"\nneuro \"next\"\n";
+    let plain = parse(tokenize(src).unwrap());
+    let marked = parse(crate::lexer::tokenize_with_lines(src, 1).unwrap());
+    let ASTNode::IfStatement {
+        body,
+        elif_blocks,
+        else_body,
+        ..
+    } = &marked[1]
+    else {
+        panic!("expected an if, got {marked:?}");
+    };
+    assert_eq!(marked[0], ASTNode::Line(1));
+    assert_eq!(body[0], ASTNode::Line(2));
+    assert_eq!(elif_blocks[0].1[0], ASTNode::Line(4));
+    assert_eq!(else_body.as_ref().unwrap()[0], ASTNode::Line(6));
+    assert_eq!(marked.len(), 4);
+    assert_eq!(marked[2], ASTNode::Line(7));
+    assert_eq!(marked[3], plain[1]);
+}
+
+#[test]
+fn thousands_of_if_blocks_parse_in_linear_time() {
+    let block =
+        "if x == 1:\n    neuro \"a\"\nelif x == 2:\n    neuro \"b\"\nelse:\n    neuro \"c\"\n";
+    let src = block.repeat(5000);
+    let tokens = crate::lexer::tokenize_with_lines(&src, 1).unwrap();
+    let start = std::time::Instant::now();
+    let ast = parse(tokens);
+    // Looking past each block's `Line` marker for `elif`/`else` used to copy the rest of the
+    // stream, which took over 10s here.
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(3),
+        "{:?}",
+        start.elapsed()
+    );
+    let full = |node: &&ASTNode| match node {
+        ASTNode::IfStatement {
+            elif_blocks,
+            else_body,
+            ..
+        } => elif_blocks.len() == 1 && else_body.is_some(),
+        _ => false,
+    };
+    assert_eq!(ast.iter().filter(full).count(), 5000);
+}
+
+#[test]
+fn thousands_of_match_assignments_parse_in_linear_time() {
+    let src = "set msg = match cmd: case \"A\": \"alpha\" default: \"other\"\n".repeat(5000);
+    let tokens = tokenize(&src).unwrap();
+    let start = std::time::Instant::now();
+    let ast = parse(tokens);
+    // `match` is told apart from a variable named `match` by the token after it.
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(3),
+        "{:?}",
+        start.elapsed()
+    );
+    assert_eq!(ast.len(), 5000);
+    assert!(ast
+        .iter()
+        .all(|node| matches!(node, ASTNode::SetVar(_, Expr::Match { .. }))));
+}
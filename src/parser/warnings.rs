@@ -0,0 +1,95 @@
+//! Non-fatal findings for `neurochain --check`: code that parses but is almost certainly a
+//! mistake. Works on the token stream because an empty block never reaches the AST (the parser
+//! drops a header with nothing indented under it).
+
+use std::fmt;
+
+use crate::lexer::Token;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// Script line, when the tokens carry `Token::Line` markers.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(n) => write!(f, "⚠️ line {n}: {}", self.message),
+            None => write!(f, "⚠️ {}", self.message),
+        }
+    }
+}
+
+/// Empty `if` / `elif` / `else` / `while` / `for` / `repeat` blocks and empty `from AI:`
+/// prompts.
+pub fn warnings(tokens: &[Token]) -> Vec<ParseWarning> {
+    // Line markers are bookkeeping only; look past them.
+    let toks: Vec<(Option<usize>, &Token)> = tokens
+        .iter()
+        .scan(None, |line, tok| {
+            if let Token::Line(n) = tok {
+                *line = Some(*n);
+                return Some(None);
+            }
+            Some(Some((*line, tok)))
+        })
+        .flatten()
+        .collect();
+
+    let mut out = Vec::new();
+    let mut block_kw = None;
+    for (i, &(line, tok)) in toks.iter().enumerate() {
+        let at_statement_start = i == 0
+            || matches!(
+                toks[i - 1].1,
+                Token::Newline | Token::Indent | Token::Dedent
+            );
+        if at_statement_start {
+            block_kw = match tok {
+                Token::If => Some("if"),
+                Token::Elif => Some("elif"),
+                Token::Else => Some("else"),
+                Token::While(_) => Some("while"),
+                Token::For(_) => Some("for"),
+                Token::Repeat(_) => Some("repeat"),
+                _ => None,
+            };
+        }
+        if *tok != Token::Colon {
+            continue;
+        }
+        let next = toks.get(i + 1).map(|t| t.1);
+        let from_ai = |back: usize| {
+            i >= back && *toks[i - back].1 == Token::From && *toks[i - back + 1].1 == Token::AI
+        };
+        // `from AI:` or `from AI "model":`
+        let model_arg = i >= 1 && matches!(toks[i - 1].1, Token::String(_));
+        if from_ai(2) || (model_arg && from_ai(3)) {
+            let empty = match next {
+                None | Some(Token::Newline) => true,
+                Some(Token::String(s)) => s.trim_matches('"').trim().is_empty(),
+                _ => false,
+            };
+            if empty {
+                out.push(ParseWarning {
+                    line,
+                    message: "empty prompt after `from AI:`".into(),
+                });
+            }
+        } else if let Some(kw) = block_kw {
+            let body_follows = toks[i + 1..]
+                .iter()
+                .find(|t| *t.1 != Token::Newline)
+                .is_some_and(|t| *t.1 == Token::Indent);
+            if matches!(next, None | Some(Token::Newline)) && !body_follows {
+                out.push(ParseWarning {
+                    line,
+                    message: format!("empty `{kw}` block (nothing indented under it)"),
+                });
+            }
+        }
+    }
+    out
+}
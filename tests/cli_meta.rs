@@ -1,4 +1,5 @@
 use assert_cmd::Command;
+use predicates::prelude::*;
 use predicates::str::contains;
 
 #[test]
@@ -42,3 +43,440 @@ fn cli_interactive_meta_commands_work() {
         .stdout(contains("macro from AI:"))
         .stdout(contains("Exiting"));
 }
+
+#[test]
+fn cli_interactive_vars_lists_variables_in_order() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+
+    cmd.write_stdin("vars\n\nset b = 2\nset a = \"hi\"\n\nvars\n\nexit\n\n")
+        .assert()
+        .success()
+        .stdout(contains("(no variables set)"))
+        .stdout(contains("b = 2\na = hi"));
+}
+
+#[test]
+fn cli_interactive_reset_clears_variables() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+
+    cmd.write_stdin("set x = \"1\"\n\nreset\n\nneuro x\n\nexit\n\n")
+        .assert()
+        .success()
+        .stdout(contains("Interpreter reset"))
+        // `x` is gone, so `neuro x` falls back to printing the name.
+        .stdout(contains("neuro: x"))
+        .stdout(contains("neuro: 1").not());
+}
+
+#[test]
+fn cli_interactive_eof_runs_partial_block_and_exits() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+
+    // No trailing blank line and no `exit`: stdin just ends.
+    cmd.write_stdin("neuro \"before eof\"")
+        .timeout(std::time::Duration::from_secs(30))
+        .assert()
+        .success()
+        .stdout(contains("neuro: before eof"))
+        .stdout(contains("Exiting"));
+}
+
+#[test]
+fn cli_define_params_are_readable_with_param() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("params.nc");
+    std::fs::write(
+        &script,
+        "neuro \"Hello \" + param(\"name\")\nneuro \"[\" + param(\"missing\") + \"]\"\n",
+    )
+    .expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["-D", "name=World"])
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("neuro: Hello World"))
+        .stdout(contains("neuro: []"));
+
+    #[allow(deprecated)]
+    let mut bad = Command::cargo_bin("neurochain").expect("bin build");
+    bad.args(["-D", "novalue"])
+        .arg(&script)
+        .assert()
+        .code(2)
+        .stderr(contains("key=value"));
+}
+
+#[test]
+fn cli_input_dir_runs_every_script_and_reports_failures() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("a_ok.nc"), "neuro \"from a\"\n").expect("write a");
+    std::fs::write(dir.path().join("b_bad.nc"), "neuro \"unterminated\n").expect("write b");
+    std::fs::write(dir.path().join("notes.txt"), "neuro \"skipped\"\n").expect("write txt");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg("--input-dir")
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stdout(contains("a_ok.nc"))
+        .stdout(contains("from a"))
+        .stdout(contains("b_bad.nc"))
+        .stdout(contains("Batch finished: 2 run, 1 failed."))
+        .stderr(contains("Missing quote"));
+}
+
+#[test]
+fn cli_quiet_drops_banner_and_framing_lines() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("quiet.nc");
+    std::fs::write(&script, "neuro \"only this\"\n").expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg("--quiet")
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("neuro: only this"))
+        .stdout(contains("Running script").not())
+        .stdout(contains("Script finished").not())
+        .stdout(contains("Welcome to NeuroChain").not());
+}
+
+#[test]
+fn cli_output_prefix_is_configurable() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("prefix.nc");
+    std::fs::write(&script, "neuro \"hello\"\n").expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.env("NC_OUTPUT_PREFIX", "[out] ")
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("[out] hello"))
+        .stdout(contains("neuro: hello").not());
+
+    #[allow(deprecated)]
+    let mut bare = Command::cargo_bin("neurochain").expect("bin build");
+    bare.env("NC_OUTPUT_PREFIX", "")
+        .arg("--quiet")
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^hello$").unwrap());
+}
+
+#[test]
+fn cli_check_parses_without_running() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let good = dir.path().join("good.nc");
+    // A missing model would fail at run time; --check never loads it.
+    std::fs::write(
+        &good,
+        "AI: \"missing/model.onnx\"\nset x = 1\n\nif x == 1:\n    neuro \"never printed\"\n",
+    )
+    .expect("write good");
+    let bad = dir.path().join("bad.nc");
+    std::fs::write(&bad, "neuro \"ok\"\n\nneuro \"unterminated\n").expect("write bad");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--quiet", "--check"])
+        .arg(&good)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^OK$").unwrap())
+        .stdout(contains("Model loaded").not());
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--quiet", "--check"])
+        .arg(&bad)
+        .assert()
+        .code(1)
+        .stdout(contains("neuro: ok").not())
+        .stderr(contains("Missing quote"));
+}
+
+#[test]
+fn cli_fail_on_warning_turns_check_warnings_into_failures() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("warns.nc");
+    // Valid, but the `if` has no body and the prompt is empty.
+    std::fs::write(
+        &script,
+        "set x = 1\nif x == 1:\nneuro \"after\"\n\nset mood from AI: \"\"\n",
+    )
+    .expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--quiet", "--check"])
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^OK$").unwrap())
+        .stderr(contains("line 2: empty `if` block"))
+        .stderr(contains("line 5: empty prompt"));
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--quiet", "--check", "--fail-on-warning"])
+        .arg(&script)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_match("(?m)^OK$").unwrap().not())
+        .stderr(contains("empty `if` block"));
+}
+
+#[test]
+fn cli_time_prints_execution_duration() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("timed.nc");
+    std::fs::write(&script, "neuro \"timed\"\n").expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg("--time")
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("neuro: timed"))
+        .stdout(
+            predicate::str::is_match(r"Script finished\.\r?\nExecution time: \d+(\.\d+)?\s*ms")
+                .unwrap(),
+        );
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("Execution time").not());
+}
+
+#[test]
+fn cli_color_flag_overrides_environment() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("color.nc");
+    std::fs::write(&script, "neuro \"plain\"\n").expect("write script");
+
+    // `never` wins even when the environment forces colour.
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--color", "never"])
+        .arg(&script)
+        .env("NC_COLOR", "always")
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .stdout(contains("neuro: plain"))
+        .stdout(contains("\x1b[").not());
+
+    // `always` colours a pipe and beats NO_COLOR.
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg("--color=always")
+        .arg(&script)
+        .env("NO_COLOR", "1")
+        .assert()
+        .success()
+        .stdout(contains("\x1b[96m"));
+
+    // Auto on a pipe: no colour.
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg(&script)
+        .env_remove("NC_COLOR")
+        .env_remove("NO_COLOR")
+        .env_remove("CLICOLOR_FORCE")
+        .assert()
+        .success()
+        .stdout(contains("\x1b[").not());
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--color", "sometimes"])
+        .arg(&script)
+        .assert()
+        .code(2)
+        .stderr(contains("--color expects always, never or auto"));
+}
+
+#[test]
+fn cli_neuro_raw_continues_the_line() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("raw.nc");
+    std::fs::write(
+        &script,
+        "neuro_raw \"a\"\nneuro_raw \"b\"\nneuro \"\"\nneuro_raw \"tail\"\n",
+    )
+    .expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg(&script)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^neuro: ab\r?$").unwrap())
+        // An open line is ended before the framing line.
+        .stdout(predicate::str::is_match(r"(?m)^neuro: tail\r?\nScript finished\.").unwrap());
+}
+
+#[test]
+fn cli_annotate_prefixes_output_with_source_lines() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("annotated.nc");
+    std::fs::write(
+        &script,
+        "neuro \"first\"\n\n# comment\nset t = 40\nif t > 30:\n    neuro \"hot\"\nelse:\n    neuro \"mild\"\n\nfor i in range(0, 2):\n    neuro i\n",
+    )
+    .expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--annotate", "-q"])
+        .arg(&script)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^\[L1\] neuro: first\r?$").unwrap())
+        .stdout(predicate::str::is_match(r"(?m)^\[L6\] neuro: hot\r?$").unwrap())
+        .stdout(
+            predicate::str::is_match(r"(?m)^\[L11\] neuro: 0\r?\n\[L11\] neuro: 1\r?$").unwrap(),
+        );
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg(&script)
+        .assert()
+        .success()
+        .stdout(contains("[L").not());
+}
+
+#[test]
+fn cli_continue_on_error_reports_and_skips_bad_statements() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("typo.nc");
+    std::fs::write(&script, "neuro \"first\"\nneuro 1 2\nneuro \"third\"\n").expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.args(["--continue-on-error", "-q"])
+        .arg(&script)
+        .assert()
+        .code(1)
+        .stdout(contains("neuro: first"))
+        .stdout(contains("neuro: third"))
+        .stderr(contains("❌ line 2: Unexpected token '2' after expression"))
+        .stderr(contains("Error: ❌ 1 statement failed and was skipped"));
+
+    // `--json` keeps the output of the statements that ran, but the run is not ok.
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    let out = cmd
+        .args(["--continue-on-error", "--json", "-q"])
+        .arg(&script)
+        .output()
+        .expect("run neurochain");
+    assert_eq!(out.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("JSON");
+    assert_eq!(json["ok"], false);
+    assert_eq!(json["output"], serde_json::json!(["first", "third"]));
+
+    // Without the flag the parse error stops the script before anything runs.
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    cmd.arg("-q")
+        .arg(&script)
+        .assert()
+        .code(1)
+        .stdout(contains("neuro:").not())
+        .stderr(contains("Error: ❌ Unexpected token '2'"));
+}
+
+#[test]
+fn cli_json_prints_one_object_with_the_output_lines() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("ok.nc");
+    std::fs::write(&script, "neuro \"one\"\nset n = 2 + 3\nneuro \"n = {n}\"\n").expect("write");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    let out = cmd
+        .arg("--json")
+        .arg(&script)
+        .output()
+        .expect("run neurochain");
+    assert!(out.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&out.stdout).expect("stdout is a single JSON object");
+    assert_eq!(
+        json,
+        serde_json::json!({"ok": true, "output": ["one", "n = 5"]})
+    );
+    // The framing lines move to stderr; the banner is gone.
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Running script:"), "{stderr}");
+    assert!(stderr.contains("Script finished."), "{stderr}");
+
+    let bad = dir.path().join("bad.nc");
+    std::fs::write(&bad, "neuro \"one\"\nneuro \"two\n").expect("write");
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    let out = cmd
+        .args(["--json", "-q"])
+        .arg(&bad)
+        .output()
+        .expect("run neurochain");
+    assert_eq!(out.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("JSON on error");
+    assert_eq!(json["ok"], false);
+    assert!(
+        json["error"]
+            .as_str()
+            .is_some_and(|e| e.contains("Missing quote")),
+        "{json}"
+    );
+    assert!(json.get("output").is_none());
+}
+
+#[test]
+fn cli_missing_macro_model_warns_once_per_sequence() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let script = dir.path().join("steps.nc");
+    std::fs::write(
+        &script,
+        "macro from AI: \"set x to 5 and then say Done and then say Bye\"\n",
+    )
+    .expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+    let out = cmd
+        .env("NC_MACRO_MODEL", dir.path().join("missing.onnx"))
+        .arg(&script)
+        .output()
+        .expect("run neurochain");
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("Done") && stdout.contains("Bye"),
+        "{stdout}"
+    );
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(
+        stderr.matches("Could not load macro model").count(),
+        1,
+        "{stderr}"
+    );
+    assert_eq!(stderr.matches("running fallback").count(), 1, "{stderr}");
+}
@@ -51,3 +51,28 @@ neuro "=== DSL SEMANTICS END ==="
         .stdout(contains("neuro: 3.14"))
         .stdout(contains("neuro: 4"));
 }
+
+#[test]
+fn dsl_semantics_neuro_interpolation() {
+    let mut file = NamedTempFile::new().expect("temp file");
+    std::io::Write::write_all(
+        &mut file,
+        br#"
+set name = "Ada"
+neuro "Hello {name}"
+neuro "Unknown {ghost}"
+neuro "Braces {{name}}"
+"#,
+    )
+    .expect("write script");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("neurochain").expect("bin build");
+
+    cmd.arg(file.path())
+        .assert()
+        .success()
+        .stdout(contains("neuro: Hello Ada"))
+        .stdout(contains("neuro: Unknown {ghost}"))
+        .stdout(contains("neuro: Braces {name}"));
+}
@@ -16,6 +16,8 @@ struct AnalyzeResp {
     ok: bool,
     output: String,
     logs: Vec<String>,
+    #[serde(default)]
+    model_path: Option<String>,
 }
 
 struct Server {
@@ -59,6 +61,20 @@ fn http_post_json_with_headers(
     path: &str,
     json_body: &str,
     headers: &[(&str, &str)],
+) -> (u16, String) {
+    http_request(addr, "POST", path, json_body, headers)
+}
+
+fn http_get(addr: SocketAddr, path: &str) -> (u16, String) {
+    http_request(addr, "GET", path, "", &[])
+}
+
+fn http_request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    json_body: &str,
+    headers: &[(&str, &str)],
 ) -> (u16, String) {
     let mut stream = TcpStream::connect(addr).expect("connect");
     stream
@@ -71,7 +87,7 @@ fn http_post_json_with_headers(
         .collect::<String>();
 
     let req = format!(
-        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n{extra}Connection: close\r\nContent-Length: {len}\r\n\r\n{body}",
+        "{method} {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n{extra}Connection: close\r\nContent-Length: {len}\r\n\r\n{body}",
         host = addr,
         len = json_body.len(),
         body = json_body,
@@ -303,6 +319,449 @@ fn api_analyze_requires_api_key_when_configured() {
     assert!(resp.output.contains("hi"));
 }
 
+#[test]
+fn api_analyze_reports_effective_model_path() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    // No model id and no `AI:` line -> no model used.
+    let body = json!({"model":"","content":"neuro \"hi\""}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 200);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok);
+    assert_eq!(resp.model_path, None);
+
+    let macro_model = models_dir().join("intent_macro").join("model.onnx");
+    if !macro_model.exists() {
+        eprintln!(
+            "api_analyze_reports_effective_model_path skipped: model not found at {}",
+            macro_model.display()
+        );
+        return;
+    }
+
+    let body = json!({"model":"macro","content":"neuro \"hi\""}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 200);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok);
+    let expected = format!("{}/intent_macro/model.onnx", models_dir().display());
+    assert_eq!(resp.model_path.as_deref(), Some(expected.as_str()));
+    assert!(resp
+        .logs
+        .iter()
+        .any(|l| l.contains(&format!("auto: injected AI model path {expected}"))));
+}
+
+#[test]
+fn api_analyze_times_out_and_releases_the_slot() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .env("NC_MAX_INFER", "1")
+        .env("NC_ANALYZE_TIMEOUT_MS", "200")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    // Millions of iterations: far longer than 200 ms.
+    let slow = "for i in range(0, 5000):\n    for j in range(0, 5000):\n        set k = i + j";
+    let body = json!({"model":"unknown","content":slow}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 503);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(!resp.ok);
+    assert!(resp.logs.iter().any(|l| l == "busy: analyze timed out"));
+
+    // With a single slot, this only succeeds if the timed-out run gave its permit back.
+    let body = json!({"model":"unknown","content":"neuro \"after\""}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 200);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok);
+    assert!(resp.output.contains("after"));
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelInfo {
+    id: String,
+    path: String,
+    available: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelsResp {
+    ok: bool,
+    models: Vec<ModelInfo>,
+}
+
+#[test]
+fn api_models_lists_known_ids_with_availability() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+    let api_key = "test-key-123";
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let sst2 = dir.path().join("distilbert-sst2");
+    std::fs::create_dir_all(&sst2).expect("mkdir");
+    std::fs::write(sst2.join("model.onnx"), b"").expect("write model stub");
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", dir.path())
+        .env("NC_API_KEY", api_key)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    let (status, _) = http_get(addr, "/api/models");
+    assert_eq!(status, 401);
+
+    let (status, resp_body) =
+        http_request(addr, "GET", "/api/models", "", &[("X-API-Key", api_key)]);
+    assert_eq!(status, 200);
+    let resp: ModelsResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok);
+
+    let ids: Vec<&str> = resp.models.iter().map(|m| m.id.as_str()).collect();
+    for id in [
+        "sst2",
+        "toxic",
+        "factcheck",
+        "intent",
+        "intent_stellar",
+        "macro",
+    ] {
+        assert!(ids.contains(&id), "missing model id {id} in {ids:?}");
+    }
+
+    let sst2 = resp.models.iter().find(|m| m.id == "sst2").unwrap();
+    assert!(sst2.available);
+    assert!(sst2.path.ends_with("distilbert-sst2/model.onnx"));
+    let toxic = resp.models.iter().find(|m| m.id == "toxic").unwrap();
+    assert!(!toxic.available);
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentResp {
+    ok: bool,
+    lines: Vec<String>,
+}
+
+#[test]
+fn api_recent_returns_latest_output_lines_bounded_by_cap() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .env("NC_RECENT_LINES", "3")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    let (status, resp_body) = http_get(addr, "/api/recent");
+    assert_eq!(status, 200);
+    let resp: RecentResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok);
+    assert!(resp.lines.is_empty());
+
+    // A silent run adds nothing, even though it answers with the no-output sentinel; output
+    // that happens to read like the sentinel is still real output.
+    for content in [
+        "neuro \"a1\"\nneuro \"a2\"",
+        "set x = 1",
+        "neuro \"Execution succeeded.\"\nneuro \"b2\"",
+    ] {
+        let body = json!({"model":"unknown","content":content}).to_string();
+        let (status, _) = http_post_json(addr, "/api/analyze", &body);
+        assert_eq!(status, 200);
+    }
+
+    let (status, resp_body) = http_get(addr, "/api/recent");
+    assert_eq!(status, 200);
+    let resp: RecentResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert_eq!(resp.lines, vec!["a2", "Execution succeeded.", "b2"]);
+}
+
+#[derive(Debug, Deserialize)]
+struct ClassifyResp {
+    ok: bool,
+    label: String,
+    score: f32,
+    topk: Vec<(String, f32)>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[test]
+fn api_classify_returns_label_score_and_topk() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+    let api_key = "classify-key";
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .env("NC_API_KEY", api_key)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    let key = [("X-API-Key", api_key)];
+    let body = json!({"model":"macro","text":"Show Ping 3 times"}).to_string();
+    let (status, _) = http_post_json(addr, "/api/classify", &body);
+    assert_eq!(status, 401);
+
+    let bad = json!({"model":"nope","text":"Show Ping 3 times"}).to_string();
+    let (status, resp_body) = http_post_json_with_headers(addr, "/api/classify", &bad, &key);
+    assert_eq!(status, 400);
+    let resp: ClassifyResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(!resp.ok);
+    assert!(resp.error.unwrap_or_default().contains("unknown model id"));
+
+    let macro_model = models_dir().join("intent_macro").join("model.onnx");
+    if !macro_model.exists() {
+        eprintln!(
+            "api_classify_returns_label_score_and_topk skipped: model not found at {}",
+            macro_model.display()
+        );
+        return;
+    }
+
+    let (status, resp_body) = http_post_json_with_headers(addr, "/api/classify", &body, &key);
+    assert_eq!(status, 200);
+    let resp: ClassifyResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok, "error: {:?}", resp.error);
+    assert_eq!(resp.label, "Loop");
+    assert!(
+        resp.score > 0.5 && resp.score <= 1.0,
+        "score {}",
+        resp.score
+    );
+    assert_eq!(resp.topk[0], (resp.label.clone(), resp.score));
+    assert!(resp.topk.windows(2).all(|w| w[0].1 >= w[1].1));
+}
+
+#[derive(Debug, Deserialize)]
+struct MetricsResp {
+    ok: bool,
+    inference_permits_available: usize,
+    inference_permits_total: usize,
+    requests_total: usize,
+}
+
+#[test]
+fn api_metrics_reports_permits_and_counts_requests() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .env("NC_MAX_INFER", "3")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    let metrics = || {
+        let (status, resp_body) = http_get(addr, "/api/metrics");
+        assert_eq!(status, 200);
+        serde_json::from_str::<MetricsResp>(&resp_body).expect("json parse")
+    };
+    let resp = metrics();
+    assert!(resp.ok);
+    assert_eq!(resp.inference_permits_total, 3);
+    assert_eq!(resp.inference_permits_available, 3);
+    assert_eq!(resp.requests_total, 0);
+
+    for _ in 0..2 {
+        let body = json!({"model":"unknown","content":"neuro \"hi\""}).to_string();
+        let (status, _) = http_post_json(addr, "/api/analyze", &body);
+        assert_eq!(status, 200);
+    }
+
+    // Finished runs have returned their permits.
+    let resp = metrics();
+    assert_eq!(resp.requests_total, 2);
+    assert_eq!(resp.inference_permits_available, 3);
+}
+
+#[test]
+fn api_analyze_uses_configured_no_output_sentinel() {
+    for (sentinel, expected) in [(None, "Execution succeeded."), (Some(""), "")] {
+        let port = find_free_port();
+        let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"));
+        cmd.current_dir(env!("CARGO_MANIFEST_DIR"))
+            .env("HOST", "127.0.0.1")
+            .env("PORT", port.to_string())
+            .env("NC_MODELS_DIR", models_dir())
+            .env_remove("NC_NO_OUTPUT_SENTINEL")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(s) = sentinel {
+            cmd.env("NC_NO_OUTPUT_SENTINEL", s);
+        }
+        let _server = Server {
+            child: cmd.spawn().expect("spawn neurochain-server"),
+        };
+
+        wait_for_listen(addr, Duration::from_secs(3));
+
+        let body = json!({"model":"unknown","content":"set x = 1"}).to_string();
+        let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+        assert_eq!(status, 200);
+        let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+        assert!(resp.ok);
+        assert_eq!(resp.output, expected);
+    }
+}
+
+#[test]
+fn api_analyze_request_log_reports_ip_model_and_latency() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let spawn = |port: u16, trust_proxy: &str| {
+        let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .env("HOST", "127.0.0.1")
+            .env("PORT", port.to_string())
+            .env("NC_MODELS_DIR", models_dir())
+            .env("NC_REQUEST_LOG", "1")
+            .env("NC_TRUST_PROXY", trust_proxy)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn neurochain-server");
+        Server { child }
+    };
+
+    let _server = spawn(port, "0");
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    let request_line = |resp: &AnalyzeResp| {
+        resp.logs
+            .iter()
+            .find(|l| l.starts_with("request: "))
+            .cloned()
+            .unwrap_or_else(|| panic!("no request log in {:?}", resp.logs))
+    };
+
+    let body = json!({"model":"unknown","content":"neuro \"hi\""}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 200);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    let line = request_line(&resp);
+    assert!(
+        line.starts_with("request: ip=127.0.0.1 model=unknown status=200 latency_ms="),
+        "{line}"
+    );
+    let ms: f64 = line.rsplit('=').next().unwrap().parse().expect("latency");
+    assert!(ms >= 0.0);
+
+    // Without NC_TRUST_PROXY a client-sent X-Forwarded-For is ignored; an empty script is
+    // still logged.
+    let body = json!({"content":""}).to_string();
+    let forwarded = [("X-Forwarded-For", "203.0.113.7, 10.0.0.1")];
+    let (_, resp_body) = http_post_json_with_headers(addr, "/api/analyze", &body, &forwarded);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(request_line(&resp).starts_with("request: ip=127.0.0.1 model=- status=200"));
+
+    // Behind a trusted proxy the first forwarded hop is logged.
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+    let _proxied = spawn(port, "1");
+    wait_for_listen(addr, Duration::from_secs(3));
+    let (_, resp_body) = http_post_json_with_headers(addr, "/api/analyze", &body, &forwarded);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(request_line(&resp).starts_with("request: ip=203.0.113.7 model=- status=200"));
+}
+
+#[test]
+fn healthz_returns_ok_without_api_key() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .env("NC_API_KEY", "test-key-123")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    let (status, resp_body) = http_get(addr, "/healthz");
+    assert_eq!(status, 200);
+    let resp: serde_json::Value = serde_json::from_str(&resp_body).expect("json parse");
+    assert_eq!(resp["status"], "ok");
+    assert_eq!(resp["version"], env!("CARGO_PKG_VERSION"));
+}
+
 fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     if needle.is_empty() {
         return Some(0);
@@ -311,3 +770,140 @@ fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
         .windows(needle.len())
         .position(|window| window == needle)
 }
+
+#[test]
+fn api_analyze_works_with_single_inference_thread() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .env("NC_INFER_THREADS", "1")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    for i in 0..3 {
+        let content = format!("set n = {i} * 2\nneuro \"run {{n}}\"");
+        let body = json!({"model":"unknown","content":content}).to_string();
+        let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+        assert_eq!(status, 200);
+        let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+        assert!(resp.ok);
+        assert!(resp.output.contains(&format!("run {}", i * 2)));
+    }
+
+    let macro_model = macro_model_path();
+    if !macro_model.exists() {
+        eprintln!(
+            "api_analyze_works_with_single_inference_thread: model check skipped, not found at {}",
+            macro_model.display()
+        );
+        return;
+    }
+
+    let body = json!({"model":"macro","content":"macro from AI: Show Ping 2 times"}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 200);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(resp.ok);
+    assert!(resp.output.contains("Ping"));
+}
+
+#[test]
+fn api_analyze_maps_syntax_errors_to_400_and_runtime_errors_to_ok_false() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+
+    let _server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+
+    for (content, expected) in [
+        ("neuro \"unterminated", "Missing quote"),
+        ("set x = 2 3", "Unexpected token '3'"),
+    ] {
+        let body = json!({"model":"unknown","content":content}).to_string();
+        let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+        assert_eq!(status, 400, "{content:?}");
+        let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+        assert!(!resp.ok);
+        assert!(resp.output.contains(expected), "{}", resp.output);
+    }
+
+    let body =
+        json!({"model":"unknown","content":"for i in range(0, \"x\"):\n    neuro i"}).to_string();
+    let (status, resp_body) = http_post_json(addr, "/api/analyze", &body);
+    assert_eq!(status, 200);
+    let resp: AnalyzeResp = serde_json::from_str(&resp_body).expect("json parse");
+    assert!(!resp.ok);
+    assert!(resp.output.contains("must be integers"), "{}", resp.output);
+}
+
+#[cfg(unix)]
+#[test]
+fn server_shuts_down_cleanly_on_sigterm() {
+    let port = find_free_port();
+    let addr: SocketAddr = format!("127.0.0.1:{port}").parse().unwrap();
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("neurochain-server"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("HOST", "127.0.0.1")
+        .env("PORT", port.to_string())
+        .env("NC_MODELS_DIR", models_dir())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn neurochain-server");
+    let mut server = Server { child };
+
+    wait_for_listen(addr, Duration::from_secs(3));
+    let (status, _) = http_get(addr, "/healthz");
+    assert_eq!(status, 200);
+
+    let sent = Command::new("kill")
+        .args(["-TERM", &server.child.id().to_string()])
+        .status()
+        .expect("run kill");
+    assert!(sent.success());
+
+    let start = Instant::now();
+    let exit = loop {
+        if let Some(exit) = server.child.try_wait().expect("try_wait") {
+            break exit;
+        }
+        if start.elapsed() > Duration::from_secs(5) {
+            panic!("server did not exit within 5s of SIGTERM");
+        }
+        thread::sleep(Duration::from_millis(25));
+    };
+    assert!(exit.success(), "exit status: {exit}");
+
+    let mut stdout = String::new();
+    server
+        .child
+        .stdout
+        .take()
+        .expect("stdout")
+        .read_to_string(&mut stdout)
+        .expect("read stdout");
+    assert!(stdout.contains("Shutting down..."), "stdout: {stdout}");
+}